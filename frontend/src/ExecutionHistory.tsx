@@ -23,6 +23,7 @@ interface Execution {
   id: string;
   timestamp: string;
   input: any;
+  trigger?: string;
   output?: any;
   status: "completed" | "failed";
   duration?: number;
@@ -393,6 +394,13 @@ const ExecutionHistory: React.FC = () => {
             <div className="execution-details">
               <h3>执行详情</h3>
 
+              {selectedExecution.trigger && (
+                <div className="detail-section">
+                  <h4>触发节点</h4>
+                  <pre className="json-display">{selectedExecution.trigger}</pre>
+                </div>
+              )}
+
               {selectedExecution.input && (
                 <div className="detail-section">
                   <h4>输入参数</h4>
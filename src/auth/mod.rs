@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Mutex, fs, path::Path};
+use std::{collections::HashMap, fs, path::Path, sync::Mutex};
 
 use axum::{
     extract::Request, http::{StatusCode, header}, middleware::Next, response::Response
@@ -82,19 +82,16 @@ pub fn verify_jwt(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
 pub async fn auth_middleware(mut request: Request, next: Next) -> Result<Response, StatusCode> {
     // 首先检查 Authorization header
     let auth_header = request.headers().get(header::AUTHORIZATION).and_then(|header| header.to_str().ok());
-    
-    if let Some(auth_header) = auth_header {
-        if let Some(token) = auth_header.strip_prefix("Bearer ") {
-            match verify_jwt(token) {
-                Ok(claims) => {
-                    request.extensions_mut().insert(claims);
-                    return Ok(next.run(request).await);
-                }
-                Err(_) => {} // 继续检查URL参数
-            }
-        }
+
+    // token 无效时继续检查URL参数
+    if let Some(auth_header) = auth_header
+        && let Some(token) = auth_header.strip_prefix("Bearer ")
+        && let Ok(claims) = verify_jwt(token)
+    {
+        request.extensions_mut().insert(claims);
+        return Ok(next.run(request).await);
     }
-    
+
     // 检查URL中的token参数
     let uri = request.uri();
     if let Some(query) = uri.query() {
@@ -102,12 +99,10 @@ pub async fn auth_middleware(mut request: Request, next: Next) -> Result<Respons
             if let Some(token_value) = param.strip_prefix("token=") {
                 // URL解码token
                 let token = urlencoding::decode(token_value).unwrap_or_default();
-                match verify_jwt(&token) {
-                    Ok(claims) => {
-                        request.extensions_mut().insert(claims);
-                        return Ok(next.run(request).await);
-                    }
-                    Err(_) => {} // token无效，继续检查其他认证方式
+                // token无效，继续检查其他认证方式
+                if let Ok(claims) = verify_jwt(&token) {
+                    request.extensions_mut().insert(claims);
+                    return Ok(next.run(request).await);
                 }
             }
         }
@@ -127,7 +122,7 @@ pub fn register_user(username: String, password: String) -> Result<(), String> {
     let password_hash = hash_password(&password).map_err(|_| "密码加密失败")?;
 
     users.insert(username.clone(), User { username, password_hash });
-    
+
     save_users_to_file(&users).map_err(|_| "保存用户数据失败")?;
 
     Ok(())
@@ -148,7 +143,7 @@ fn load_users_from_file() -> Result<HashMap<String, User>, Box<dyn std::error::E
     if !Path::new(USERS_FILE).exists() {
         return Ok(HashMap::new());
     }
-    
+
     let content = fs::read_to_string(USERS_FILE)?;
     let users: HashMap<String, User> = serde_json::from_str(&content)?;
     Ok(users)
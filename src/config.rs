@@ -0,0 +1,88 @@
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+const CONFIG_FILE: &str = "config.json";
+
+/// 服务端配置，从工作目录下的 config.json 读取，文件不存在时使用默认值
+pub static CONFIG: Lazy<ServerConfig> = Lazy::new(|| load_config().unwrap_or_default());
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ServerConfig {
+    #[serde(default)]
+    pub exec: ExecConfig,
+    #[serde(default)]
+    pub lua: LuaConfig,
+    #[serde(default)]
+    pub binary: BinaryConfig,
+}
+
+/// exec 节点配置，默认关闭
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExecConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 允许执行的程序，为空时不限制
+    #[serde(rename = "allowedPrograms", default)]
+    pub allowed_programs: Vec<String>,
+}
+
+/// lua-script 节点的沙箱限制，同时约束脚本可用的宿主函数
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LuaConfig {
+    /// 单次执行的最长时间（秒），节点配置的 timeout 只能更短。超时后节点立即失败，
+    /// 但正在进行的单次 C 函数调用（如复杂的模式匹配）会在后台线程中继续直到结束
+    #[serde(rename = "timeoutSecs")]
+    pub timeout_secs: u64,
+    /// 最多执行的虚拟机指令数，0 表示不限制
+    #[serde(rename = "maxInstructions")]
+    pub max_instructions: u64,
+    /// 内存上限（MB）
+    #[serde(rename = "memoryLimitMb")]
+    pub memory_limit_mb: usize,
+    /// 是否允许脚本通过 http.get / http.post 发送请求，默认关闭
+    #[serde(rename = "httpEnabled")]
+    pub http_enabled: bool,
+    /// 脚本可以访问的主机，支持 *.example.com，* 表示任意主机，为空时不能访问任何主机。
+    /// 回环、私有网段与链路本地地址（如云服务器元数据 169.254.169.254）只有明确列出主机名或 IP 时才能访问
+    #[serde(rename = "allowedHosts")]
+    pub allowed_hosts: Vec<String>,
+    /// 每个工作流在 kv 中最多保存的键数
+    #[serde(rename = "kvMaxKeys")]
+    pub kv_max_keys: usize,
+    /// 每个工作流在 kv 中保存的数据上限（KB，按 JSON 大小计算）
+    #[serde(rename = "kvMaxSizeKb")]
+    pub kv_max_size_kb: usize,
+}
+
+impl Default for LuaConfig {
+    fn default() -> Self {
+        LuaConfig { timeout_secs: 10, max_instructions: 100_000_000, memory_limit_mb: 64, http_enabled: false, allowed_hosts: vec![], kv_max_keys: 1000, kv_max_size_kb: 1024 }
+    }
+}
+
+/// 节点间传递的二进制数据的保存期限
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BinaryConfig {
+    /// 保存时间（小时），超过后删除，执行记录中的引用随之失效；0 表示永久保存
+    #[serde(rename = "ttlHours")]
+    pub ttl_hours: u64,
+}
+
+impl Default for BinaryConfig {
+    fn default() -> Self {
+        BinaryConfig { ttl_hours: 24 * 7 }
+    }
+}
+
+fn load_config() -> Option<ServerConfig> {
+    let content = std::fs::read_to_string(CONFIG_FILE).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            log::error!("解析 {} 失败: {}", CONFIG_FILE, e);
+            None
+        }
+    }
+}
@@ -3,6 +3,7 @@ use axum::{
 };
 use tower_http::cors::{Any, CorsLayer};
 mod auth;
+mod config;
 mod error;
 mod workflow;
 use mime_guess::from_path;
@@ -21,9 +22,19 @@ async fn main() -> anyhow::Result<()> {
         .route("/workflow/run", post(workflow::execute_workflow))
         .route("/workflow/{id}", get(workflow::get))
         .route("/workflow/{id}", delete(workflow::delete))
-        .route("/workflow/{id}/run", get(workflow::execute))
+        .route(
+            "/workflow/{id}/run",
+            get(workflow::execute).post(workflow::execute),
+        )
         .route("/workflow/{id}/history", get(workflow::get_executions))
-        .route("/v1/{*path}", get(workflow::execute_path))
+        .route(
+            "/v1/{*path}",
+            get(workflow::execute_path).post(workflow::execute_path),
+        )
+        .route("/binary/{id}", get(workflow::binary::get))
+        .route("/lua/check", post(workflow::check_lua_script))
+        .route("/approvals", get(workflow::approval::list))
+        .route("/approvals/{id}", post(workflow::approval::decide))
         .route_layer(middleware::from_fn(auth::auth_middleware));
 
     let api_router = Router::new().route("/health", get(|| async { "OK" })).route("/login", post(auth::handlers::login)).route("/register", post(auth::handlers::register)).merge(protected_routes);
@@ -34,6 +45,10 @@ async fn main() -> anyhow::Result<()> {
         .layer(DefaultBodyLimit::max(50 * 1024 * 1024)) // 50MB limit
         .layer(cors);
 
+    workflow::approval::abort_pending().await;
+    workflow::start_triggers().await;
+    workflow::binary::start_cleanup();
+
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3333").await?;
     axum::serve(listener, app).await?;
     Ok(())
@@ -0,0 +1,216 @@
+use std::{
+    collections::HashMap, sync::{Arc, OnceLock}, time::Duration
+};
+
+use axum::{Extension, Json, extract::Path};
+use chrono::{DateTime, Utc};
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{RwLock, oneshot};
+
+use super::model::{Execution, Log, LogData};
+use crate::{auth::Claims, error::AppError};
+
+static APPROVALS: OnceLock<Arc<RwLock<HashMap<String, PendingApproval>>>> = OnceLock::new();
+/// 待审批条目同时写入文件，服务重启后据此把对应的执行记录为已中止
+#[cfg(not(test))]
+static APPROVAL_FILE: &str = "approvals.json";
+#[cfg(test)]
+static APPROVAL_FILE: &str = "target/approvals-test.json";
+
+/// 等待人工审批的条目
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Approval {
+    pub id: String,
+    #[serde(rename = "workflowId")]
+    pub workflow_id: String,
+    #[serde(rename = "executionId")]
+    pub execution_id: String,
+    #[serde(rename = "nodeId")]
+    pub node_id: String,
+    pub message: String,
+    pub payload: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// 审批结果
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApprovalDecision {
+    pub approved: bool,
+    pub payload: String,
+    pub comment: Option<String>,
+    pub username: String,
+    #[serde(rename = "decidedAt")]
+    pub decided_at: DateTime<Utc>,
+}
+
+/// 审批请求，payload 为空时沿用原始内容
+#[derive(Deserialize)]
+pub struct ApprovalReqParam {
+    pub approved: bool,
+    pub payload: Option<String>,
+    pub comment: Option<String>,
+}
+
+struct PendingApproval {
+    approval: Approval,
+    responder: oneshot::Sender<ApprovalDecision>,
+}
+
+fn approvals() -> &'static Arc<RwLock<HashMap<String, PendingApproval>>> {
+    APPROVALS.get_or_init(|| Arc::new(RwLock::new(HashMap::new())))
+}
+
+fn save_pending(data: &HashMap<String, PendingApproval>) {
+    let mut pending: Vec<&Approval> = data.values().map(|p| &p.approval).collect();
+    pending.sort_by_key(|a| a.created_at);
+    let result = serde_json::to_string_pretty(&pending).map_err(anyhow::Error::from).and_then(|json_string| Ok(std::fs::write(APPROVAL_FILE, json_string)?));
+    if let Err(e) = result {
+        error!("保存 {} 失败: {}", APPROVAL_FILE, e);
+    }
+}
+
+/// 挂起当前执行直到有人审批，timeout 为 None 时一直等待；超时返回 None
+pub async fn wait_for_decision(approval: Approval, timeout: Option<Duration>) -> Option<ApprovalDecision> {
+    let id = approval.id.clone();
+    let (responder, receiver) = oneshot::channel();
+    {
+        let mut data = approvals().write().await;
+        data.insert(id.clone(), PendingApproval { approval, responder });
+        save_pending(&data);
+    }
+
+    let decision = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, receiver).await.ok().and_then(|r| r.ok()),
+        None => receiver.await.ok(),
+    };
+
+    // 超时后清理条目，避免继续出现在待审批列表中
+    let mut data = approvals().write().await;
+    if data.remove(&id).is_some() {
+        save_pending(&data);
+    }
+    decision
+}
+
+/// 启动时处理上次运行遗留的待审批条目：等待中的执行已随进程结束，为每个条目记录一次状态为 aborted 的执行
+pub async fn abort_pending() {
+    let Ok(json_string) = std::fs::read_to_string(APPROVAL_FILE) else {
+        return;
+    };
+    let pending: Vec<Approval> = match serde_json::from_str(&json_string) {
+        Ok(pending) => pending,
+        Err(e) => {
+            error!("解析 {} 失败: {}", APPROVAL_FILE, e);
+            return;
+        }
+    };
+    for approval in pending {
+        warn!(
+            "服务重启，中止工作流 {} 执行 {} 中未完成的审批 {}",
+            approval.workflow_id, approval.execution_id, approval.id
+        );
+        let now = Utc::now();
+        let log_data =
+            LogData {
+                kind: "approval_aborted".to_string(), node_id: approval.node_id.clone(), node_type: Some("approval".to_string()), result: None, data: Some("服务重启，审批已中止".to_string())
+            };
+        super::create_execution(Execution {
+            id: approval.execution_id,
+            workflow_id: approval.workflow_id,
+            input: HashMap::new(),
+            trigger: None,
+            logs: vec![Log { timestamp: now, data: log_data }],
+            duration: (now - approval.created_at).num_milliseconds(),
+            status: "aborted".to_string(),
+            timestamp: approval.created_at,
+            variables: HashMap::new(),
+        })
+        .await;
+    }
+    save_pending(&HashMap::new());
+}
+
+pub async fn list() -> Result<Json<Vec<Approval>>, AppError> {
+    let data = approvals().read().await;
+    let mut response: Vec<Approval> = data.values().map(|p| p.approval.clone()).collect();
+    response.sort_by_key(|a| a.created_at);
+    Ok(Json(response))
+}
+
+pub async fn decide(Path(id): Path<String>, Extension(claims): Extension<Claims>, Json(param): Json<ApprovalReqParam>) -> Result<Json<ApprovalDecision>, AppError> {
+    let pending = {
+        let mut data = approvals().write().await;
+        let pending = data.remove(&id).ok_or_else(|| AppError::NotFound(format!("审批不存在: id={}", id)))?;
+        save_pending(&data);
+        pending
+    };
+
+    let decision = ApprovalDecision { approved: param.approved, payload: param.payload.unwrap_or(pending.approval.payload), comment: param.comment, username: claims.username, decided_at: Utc::now() };
+
+    pending.responder.send(decision.clone()).map_err(|_| AppError::Conflict(format!("审批对应的执行已结束: id={}", id)))?;
+    Ok(Json(decision))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approval(id: &str) -> Approval {
+        Approval {
+            id: id.to_string(),
+            workflow_id: "w".to_string(),
+            execution_id: "e".to_string(),
+            node_id: "approval".to_string(),
+            message: "上线?".to_string(),
+            payload: "original".to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn claims() -> Extension<Claims> {
+        Extension(Claims { username: "alice".to_string(), exp: 0 })
+    }
+
+    async fn pending_ids() -> Vec<String> {
+        list().await.unwrap().0.into_iter().map(|a| a.id).collect()
+    }
+
+    #[tokio::test]
+    async fn decide_resumes_waiting_execution() {
+        let waiting = tokio::spawn(wait_for_decision(approval("decide-1"), None));
+        while !pending_ids().await.contains(&"decide-1".to_string()) {
+            tokio::task::yield_now().await;
+        }
+        let param = ApprovalReqParam { approved: true, payload: None, comment: Some("ok".to_string()) };
+        let Json(decision) = decide(Path("decide-1".to_string()), claims(), Json(param)).await.unwrap();
+        assert_eq!(
+            (
+                decision.approved,
+                decision.payload.as_str(),
+                decision.username.as_str()
+            ),
+            (true, "original", "alice")
+        );
+
+        let received = waiting.await.unwrap().unwrap();
+        assert_eq!(received.comment.as_deref(), Some("ok"));
+        assert!(!pending_ids().await.contains(&"decide-1".to_string()));
+    }
+
+    #[tokio::test]
+    async fn times_out_and_removes_entry() {
+        assert!(wait_for_decision(approval("timeout-1"), Some(Duration::from_millis(20))).await.is_none());
+        assert!(!pending_ids().await.contains(&"timeout-1".to_string()));
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_id() {
+        let param = ApprovalReqParam { approved: false, payload: None, comment: None };
+        match decide(Path("missing".to_string()), claims(), Json(param)).await {
+            Err(AppError::NotFound(message)) => assert_eq!(message, "审批不存在: id=missing"),
+            other => panic!("{:?}", other.map(|json| json.0)),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::{io::ErrorKind, path::PathBuf, time::Duration};
+
+use anyhow::anyhow;
+use axum::{Json, extract::Path};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{config::CONFIG, error::AppError};
+
+#[cfg(not(test))]
+static BINARY_DIR: &str = "binaries";
+#[cfg(test)]
+static BINARY_DIR: &str = "target/binaries-test";
+static BINARY_KIND: &str = "binary";
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 节点间传递的二进制数据（图片、PDF 等）。内容保存在 binaries 目录，
+/// 节点输出与执行记录中只包含这个引用：{"type": "binary", "id", "fileName", "mimeType", "size"}
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Binary {
+    #[serde(rename = "type")]
+    kind: String,
+    pub id: String,
+    #[serde(rename = "fileName")]
+    pub file_name: String,
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+    pub size: u64,
+}
+
+impl Binary {
+    /// 保存内容并返回引用，未指定 MIME 类型时按文件名推断
+    pub fn save(content: &[u8], file_name: Option<&str>, mime_type: Option<&str>) -> anyhow::Result<Binary> {
+        let file_name = file_name.and_then(|name| std::path::Path::new(name).file_name()).map(|name| name.to_string_lossy().to_string()).filter(|name| !name.is_empty()).unwrap_or("file".to_string());
+        let mime_type = mime_type.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).unwrap_or_else(|| mime_guess::from_path(&file_name).first_or_octet_stream().to_string());
+        let id = uuid::Uuid::new_v4().to_string();
+        std::fs::create_dir_all(BINARY_DIR)?;
+        std::fs::write(path(&id), content)?;
+        let binary = Binary { kind: BINARY_KIND.to_string(), id, file_name, mime_type, size: content.len() as u64 };
+        // 引用信息另存一份，供 API 按 id 查询
+        std::fs::write(path(&binary.id).with_extension("json"), binary.to_output())?;
+        Ok(binary)
+    }
+
+    /// 节点输出或配置值为二进制引用时解析出引用
+    pub fn parse(text: &str) -> Option<Binary> {
+        if !text.trim_start().starts_with('{') {
+            return None;
+        }
+        serde_json::from_str::<Binary>(text).ok().filter(|binary| binary.kind == BINARY_KIND && uuid::Uuid::parse_str(&binary.id).is_ok())
+    }
+
+    pub fn read(&self) -> anyhow::Result<Vec<u8>> {
+        std::fs::read(path(&self.id)).map_err(|e| anyhow!("读取二进制数据 {} 失败: {}", self.id, e))
+    }
+
+    /// 内容的保存路径
+    pub fn path(&self) -> PathBuf {
+        path(&self.id)
+    }
+
+    /// 作为节点输出的引用字符串
+    pub fn to_output(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// 解析 base64 或 data URL（data:image/png;base64,...）编码的内容，用于 API 传入
+    pub fn decode(value: &str, file_name: Option<&str>) -> anyhow::Result<Binary> {
+        let (mime_type, encoded) = match value.split_once(";base64,") {
+            Some((prefix, data)) if prefix.starts_with("data:") => (Some(&prefix[5..]), data),
+            _ => (None, value),
+        };
+        let content = STANDARD.decode(encoded.trim()).map_err(|e| anyhow!("不是合法的 base64 内容: {}", e))?;
+        Binary::save(&content, file_name, mime_type)
+    }
+}
+
+fn path(id: &str) -> PathBuf {
+    PathBuf::from(BINARY_DIR).join(id)
+}
+
+/// 启动时以及此后每小时删除超过 config.json 中 binary.ttlHours 的二进制数据，ttlHours 为 0 时不清理
+pub fn start_cleanup() {
+    let ttl_hours = CONFIG.binary.ttl_hours;
+    if ttl_hours == 0 {
+        return;
+    }
+    let ttl = Duration::from_secs(ttl_hours * 3600);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            match tokio::task::spawn_blocking(move || cleanup(ttl)).await {
+                Ok(Ok(0)) => {}
+                Ok(Ok(removed)) => info!("已删除 {} 个过期的二进制数据", removed),
+                Ok(Err(e)) => error!("清理二进制数据失败: {}", e),
+                Err(e) => error!("清理二进制数据失败: {}", e),
+            }
+        }
+    });
+}
+
+/// 按修改时间删除内容及其引用信息，返回删除的二进制数据个数
+fn cleanup(ttl: Duration) -> anyhow::Result<usize> {
+    let entries = match std::fs::read_dir(BINARY_DIR) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let mut removed = 0;
+    for entry in entries {
+        let entry = entry?;
+        if !entry.metadata()?.modified()?.elapsed().is_ok_and(|age| age > ttl) {
+            continue;
+        }
+        let path = entry.path();
+        std::fs::remove_file(&path)?;
+        if path.extension().is_none() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// 获取二进制数据：引用信息以及 base64 编码的内容
+pub async fn get(Path(id): Path<String>) -> Result<Json<Value>, AppError> {
+    if uuid::Uuid::parse_str(&id).is_err() {
+        return Err(AppError::BadRequest(format!("无效的二进制数据 id: {}", id)));
+    }
+    // 已过期删除的数据同样返回不存在
+    let binary =
+        std::fs::read_to_string(path(&id).with_extension("json")).ok().and_then(|json_string| Binary::parse(&json_string)).ok_or_else(|| AppError::NotFound(format!("二进制数据不存在: id={}", id)))?;
+    let content = binary.read().map_err(AppError::Internal)?;
+    let mut response = serde_json::to_value(&binary)?;
+    response["data"] = json!(STANDARD.encode(content));
+    Ok(Json(response))
+}
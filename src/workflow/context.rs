@@ -0,0 +1,51 @@
+use std::{
+    collections::HashMap, sync::{Arc, Mutex}
+};
+
+use serde_json::Value;
+
+/// 单次工作流执行的上下文，在各节点间共享
+#[derive(Debug, Clone)]
+pub struct Context {
+    pub execution_id: String,
+    pub workflow_id: String,
+    /// 已校验的工作流参数
+    pub params: HashMap<String, String>,
+    /// 执行范围内的变量，由 set-variables 节点写入
+    variables: Arc<Mutex<HashMap<String, Value>>>,
+    /// 由触发器启动时的触发事件
+    pub trigger: Option<TriggerEvent>,
+    /// 已执行节点的输出，按节点 id 保存最近一次的结果
+    outputs: Arc<Mutex<HashMap<String, String>>>,
+}
+
+/// 触发本次执行的触发器节点及其事件数据
+#[derive(Debug, Clone)]
+pub struct TriggerEvent {
+    pub node_id: String,
+    pub data: Value,
+}
+
+impl Context {
+    pub fn new(workflow_id: String, params: HashMap<String, String>) -> Self {
+        Context { execution_id: uuid::Uuid::new_v4().to_string(), workflow_id, params, variables: Arc::new(Mutex::new(HashMap::new())), trigger: None, outputs: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    /// 当前变量的快照
+    pub fn variables(&self) -> HashMap<String, Value> {
+        self.variables.lock().unwrap().clone()
+    }
+
+    pub fn set_variable(&self, name: String, value: Value) {
+        self.variables.lock().unwrap().insert(name, value);
+    }
+
+    /// 已执行节点输出的快照
+    pub fn outputs(&self) -> HashMap<String, String> {
+        self.outputs.lock().unwrap().clone()
+    }
+
+    pub fn set_output(&self, node_id: String, output: String) {
+        self.outputs.lock().unwrap().insert(node_id, output);
+    }
+}
@@ -1,31 +1,48 @@
 use std::{
-    collections::HashMap, convert::Infallible, sync::{Arc, OnceLock}
+    collections::{HashMap, HashSet, VecDeque}, convert::Infallible, sync::{Arc, OnceLock}
 };
 
+use anyhow::anyhow;
 use axum::{
     Json, body::Body, extract::{Path, Query}, http::header, response::{IntoResponse, Response, Sse, sse::Event}
 };
 use chrono::Utc;
 use log::info;
+use serde_json::{Value, json};
 use tokio::sync::{
     RwLock, mpsc, mpsc::{UnboundedReceiver, UnboundedSender}
 };
 use tokio_stream::wrappers::UnboundedReceiverStream;
-use uuid::Uuid;
 
 use crate::error::AppError;
 
 static WORKFLOWS: OnceLock<Arc<RwLock<Vec<Workflow>>>> = OnceLock::new();
-static WORKFLOW_FILE: &'static str = "workflows.json";
+static WORKFLOW_FILE: &str = "workflows.json";
 static EXECUTIONS: OnceLock<Arc<RwLock<Vec<Execution>>>> = OnceLock::new();
-static EXECUTION_FILE: &'static str = "executions.json";
-
+static EXECUTION_FILE: &str = "executions.json";
+/// 节点失败时错误信息沿该输出连接点继续
+static ERROR_HANDLE: &str = "error";
+/// exec 节点除 success / failure 外，无论结果如何都会沿该输出连接点继续
+static EXEC_ALWAYS_HANDLE: &str = "always";
+
+pub mod approval;
+pub mod binary;
+mod context;
 mod model;
 mod node;
+mod parameter;
 mod sse;
+mod stream;
+mod tls;
+mod trigger;
+use context::{Context, TriggerEvent};
 use model::{Execution, Log, LogData, Node, Workflow, WorkflowReqParam};
+pub use node::lua_script::check as check_lua_script;
+
+/// 节点 id 与连接点（handle）
+type NodeHandle = (String, Option<String>);
 
-/// 执行记录增查
+// 执行记录增查
 
 fn load_execution_config() -> Vec<Execution> {
     if !std::path::Path::new(EXECUTION_FILE).exists() {
@@ -48,11 +65,11 @@ async fn create_execution(execution: Execution) {
 
 pub async fn get_executions(Path(id): Path<String>) -> Result<Json<Vec<Execution>>, AppError> {
     let data = EXECUTIONS.get_or_init(|| Arc::new(RwLock::new(load_execution_config()))).read().await;
-    let executions: Vec<Execution> = data.iter().filter(|exe| exe.workflow_id == id).map(|exe| exe.clone()).rev().collect();
+    let executions: Vec<Execution> = data.iter().filter(|exe| exe.workflow_id == id).cloned().rev().collect();
     Ok(Json(executions))
 }
 
-/// 工作流增删改查
+// 工作流增删改查
 
 fn load_config() -> Vec<Workflow> {
     if !std::path::Path::new(WORKFLOW_FILE).exists() {
@@ -68,30 +85,25 @@ fn save_config(workflows: &Vec<Workflow>) {
 }
 
 pub async fn create_or_update(Json(mut workflow): Json<Workflow>) -> Result<Json<Workflow>, AppError> {
-    if workflow.id.is_some() {
+    if let Some(id) = workflow.id.clone() {
         let updated_at = Utc::now();
         workflow.updated_at = Some(updated_at);
         let mut data = WORKFLOWS.get_or_init(|| Arc::new(RwLock::new(load_config()))).write().await;
         if let Some(index) = data.iter().position(|w| w.id == workflow.id) {
-            match data.iter().position(|w| w.name == workflow.name) {
-                Some(idx) => {
-                    if idx != index {
-                        return Err(AppError::Conflict(format!(
-                            "工作流路径已存在：path={}",
-                            workflow.name
-                        )));
-                    }
-                }
-                None => {}
+            if let Some(idx) = data.iter().position(|w| w.name == workflow.name)
+                && idx != index
+            {
+                return Err(AppError::Conflict(format!(
+                    "工作流路径已存在：path={}",
+                    workflow.name
+                )));
             };
             data[index] = workflow.clone();
             save_config(&data);
+            trigger::sync(&data);
             return Ok(Json(workflow));
         } else {
-            return Err(AppError::NotFound(format!(
-                "Workflow 不存在: id={}",
-                workflow.id.unwrap()
-            )));
+            return Err(AppError::NotFound(format!("Workflow 不存在: id={}", id)));
         }
     }
     workflow.id = Some(uuid::Uuid::new_v4().to_string());
@@ -100,7 +112,7 @@ pub async fn create_or_update(Json(mut workflow): Json<Workflow>) -> Result<Json
     workflow.created_at = Some(created_at);
     workflow.updated_at = Some(updated_at);
     let mut data = WORKFLOWS.get_or_init(|| Arc::new(RwLock::new(load_config()))).write().await;
-    if let Some(_) = data.iter().position(|w| w.id == workflow.id) {
+    if data.iter().any(|w| w.id == workflow.id) {
         Err(AppError::Conflict(format!(
             "Workflow 已存在: id={}",
             workflow.id.unwrap()
@@ -108,6 +120,7 @@ pub async fn create_or_update(Json(mut workflow): Json<Workflow>) -> Result<Json
     } else {
         data.push(workflow.clone());
         save_config(&data);
+        trigger::sync(&data);
         Ok(Json(workflow))
     }
 }
@@ -117,6 +130,7 @@ pub async fn delete(Path(id): Path<String>) -> Result<(), AppError> {
     if let Some(index) = data.iter().position(|w| w.id == Some(id.clone())) {
         data.remove(index);
         save_config(&data);
+        trigger::sync(&data);
         Ok(())
     } else {
         Err(AppError::NotFound(format!("Workflow 不存在: id={}", id)))
@@ -135,10 +149,16 @@ pub async fn get(Path(id): Path<String>) -> Result<Json<Workflow>, AppError> {
     if let Some(workflow) = data.iter().find(|w| w.id == Some(id.clone())) { Ok(Json(workflow.clone())) } else { Err(AppError::NotFound(format!("Workflow 不存在: id={}", id))) }
 }
 
-/// 执行工作流
+/// 启动已保存工作流中的触发器
+pub async fn start_triggers() {
+    let data = WORKFLOWS.get_or_init(|| Arc::new(RwLock::new(load_config()))).read().await;
+    trigger::sync(&data);
+}
+
+// 执行工作流
 
 /// 根据路径执行工作流
-pub async fn execute_path(Path(path): Path<String>, Query(param): Query<WorkflowReqParam>) -> Response<Body> {
+pub async fn execute_path(Path(path): Path<String>, Query(param): Query<WorkflowReqParam>, body: Option<Json<HashMap<String, Value>>>) -> Response<Body> {
     let data = WORKFLOWS.get_or_init(|| Arc::new(RwLock::new(load_config()))).read().await;
     let index = match data.iter().position(|w| w.name == path.clone()) {
         Some(idx) => idx,
@@ -153,30 +173,23 @@ pub async fn execute_path(Path(path): Path<String>, Query(param): Query<Workflow
     let workflow = data[index].clone(); // 克隆 workflow 以避免锁持有太久
     drop(data); // 尽早释放锁
 
-    if workflow.nodes.iter().any(|node| node.kind == "output") {
-        let output = run_workflow(workflow, None, true, param.input).await.unwrap();
-        return (axum::http::StatusCode::OK, output).into_response();
-    }
-
-    let (sender, receiver) = mpsc::unbounded_channel();
-
-    tokio::spawn(async move {
-        let _ = run_workflow(workflow, Some(sender), true, param.input).await;
-    });
-
-    sse_response(receiver).into_response()
+    trigger(workflow, merge_body(param, body)).await
 }
 
-pub async fn execute_workflow(Json(workflow): Json<Workflow>) -> impl IntoResponse {
+pub async fn execute_workflow(Query(param): Query<WorkflowReqParam>, Json(workflow): Json<Workflow>) -> Response<Body> {
+    let params = match parameter::resolve(&workflow.parameters, &param.params) {
+        Ok(params) => params,
+        Err(e) => return AppError::BadRequest(e).into_response(),
+    };
     let (sender, receiver) = mpsc::unbounded_channel();
     tokio::spawn(async move {
-        let _ = run_workflow(workflow, Some(sender), false, None).await;
+        let _ = run_workflow(workflow, Some(sender), false, param.input, params, None).await;
     });
 
-    sse_response(receiver)
+    sse_response(receiver).into_response()
 }
 
-pub async fn execute(Path(id): Path<String>, Query(param): Query<WorkflowReqParam>) -> Response<Body> {
+pub async fn execute(Path(id): Path<String>, Query(param): Query<WorkflowReqParam>, body: Option<Json<HashMap<String, Value>>>) -> Response<Body> {
     let data = WORKFLOWS.get_or_init(|| Arc::new(RwLock::new(load_config()))).read().await;
     let index = match data.iter().position(|w| w.id == Some(id.clone())) {
         Some(idx) => idx,
@@ -191,26 +204,69 @@ pub async fn execute(Path(id): Path<String>, Query(param): Query<WorkflowReqPara
     let workflow = data[index].clone(); // 克隆 workflow 以避免锁持有太久
     drop(data); // 尽早释放锁
 
+    trigger(workflow, merge_body(param, body)).await
+}
+
+/// POST 请求体中的参数覆盖查询参数
+fn merge_body(mut param: WorkflowReqParam, body: Option<Json<HashMap<String, Value>>>) -> WorkflowReqParam {
+    if let Some(Json(body)) = body {
+        for (key, value) in body {
+            let value = match value {
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+            if key == "input" {
+                param.input = Some(value);
+            } else {
+                param.params.insert(key, value);
+            }
+        }
+    }
+    param
+}
+
+/// 校验参数后执行已保存的工作流：包含输出节点时直接返回结果，否则以 SSE 推送执行过程
+async fn trigger(workflow: Workflow, param: WorkflowReqParam) -> Response<Body> {
+    let params = match parameter::resolve(&workflow.parameters, &param.params) {
+        Ok(params) => params,
+        Err(e) => return AppError::BadRequest(e).into_response(),
+    };
+
     if workflow.nodes.iter().any(|node| node.kind == "output") {
-        let output = run_workflow(workflow, None, true, param.input).await.unwrap();
-        return (axum::http::StatusCode::OK, output).into_response();
+        // 节点失败且未连接 error 连接点时返回 500 与错误信息
+        return match run_workflow(workflow, None, true, param.input, params, None).await {
+            Ok(output) => (axum::http::StatusCode::OK, output).into_response(),
+            Err(e) => AppError::Internal(e).into_response(),
+        };
     }
 
     let (sender, receiver) = mpsc::unbounded_channel();
 
     tokio::spawn(async move {
-        let _ = run_workflow(workflow, Some(sender), true, param.input).await;
+        let _ = run_workflow(workflow, Some(sender), true, param.input, params, None).await;
     });
 
     sse_response(receiver).into_response()
 }
 
-async fn run_workflow(workflow: Workflow, sender: Option<UnboundedSender<Result<Event, Infallible>>>, record_execution: bool, input: Option<String>) -> anyhow::Result<String> {
+/// 由触发器事件执行已保存的工作流，参数取默认值，记录执行历史
+async fn run_trigger(workflow_id: &str, event: TriggerEvent) -> anyhow::Result<String> {
+    let data = WORKFLOWS.get_or_init(|| Arc::new(RwLock::new(load_config()))).read().await;
+    let workflow = data.iter().find(|w| w.id.as_deref() == Some(workflow_id)).cloned().ok_or_else(|| anyhow!("工作流不存在：id={}", workflow_id))?;
+    drop(data);
+
+    let params = parameter::resolve(&workflow.parameters, &HashMap::new()).map_err(|e| anyhow!(e))?;
+    run_workflow(workflow, None, true, None, params, Some(event)).await
+}
+
+async fn run_workflow(
+    workflow: Workflow, sender: Option<UnboundedSender<Result<Event, Infallible>>>, record_execution: bool, input: Option<String>, params: HashMap<String, String>, trigger_event: Option<TriggerEvent>,
+) -> anyhow::Result<String> {
     let mut result = String::new();
     let edges = workflow.edges.clone();
 
     // 创建节点连接映射，支持条件节点的 true/false 输出
-    let mut next_node_map: HashMap<(String, Option<String>), Vec<(String, Option<String>)>> = HashMap::new();
+    let mut next_node_map: HashMap<NodeHandle, Vec<NodeHandle>> = HashMap::new();
     for edge in &edges {
         let source = edge.source.clone();
         let source_handle = edge.source_handle.clone();
@@ -222,22 +278,85 @@ async fn run_workflow(workflow: Workflow, sender: Option<UnboundedSender<Result<
 
     info!("完整的next_node_map: {:?}", next_node_map);
 
-    let mut nodes = workflow.nodes.clone();
+    let nodes = &workflow.nodes;
 
     // 找出起始节点（没有前驱的节点）
     let target_nodes: Vec<String> = edges.iter().map(|edge| edge.target.clone()).collect();
     let mut start_nodes: Vec<String> = nodes.iter().filter(|node| !target_nodes.contains(&node.id)).map(|node| node.id.clone()).collect();
+    // 由触发器启动时，其他触发器节点不参与本次执行
+    if let Some(event) = &trigger_event {
+        start_nodes.retain(|node_id| node_id == &event.node_id || nodes.iter().any(|n| n.id == *node_id && !trigger::is_trigger(&n.kind)));
+    }
 
+    // 记录本次执行实际使用的参数
+    let mut execution_input = params.clone();
     let mut input_map: HashMap<String, Vec<(Option<String>, String)>> = HashMap::new();
     if let Some(input) = input {
+        execution_input.entry("input".to_string()).or_insert(input.clone());
         for node_id in start_nodes.clone() {
             input_map.entry(node_id).or_default().push((None, input.clone()));
         }
     }
 
+    let trigger_node_id = trigger_event.as_ref().map(|event| event.node_id.clone());
+    let mut ctx = Context::new(
+        workflow.id.clone().unwrap_or_else(|| "unknown".to_string()),
+        params,
+    );
+    ctx.trigger = trigger_event;
     let mut logs = Vec::new();
     let start_time = Utc::now();
+
+    let graph = Graph { nodes: workflow.nodes.clone(), next_node_map };
+    let run = run_nodes(
+        &graph,
+        start_nodes,
+        input_map,
+        &ctx,
+        &sender,
+        &mut logs,
+        &mut result,
+    )
+    .await;
+
+    // 记录执行历史（仅当 record_execution 为 true），失败的执行也会记录，错误信息在日志中
+    if record_execution {
+        let end_time = Utc::now();
+        let execution = Execution {
+            id: ctx.execution_id.clone(),
+            status: if run.is_ok() { "completed" } else { "failed" }.to_string(),
+            workflow_id: ctx.workflow_id.clone(),
+            input: execution_input,
+            trigger: trigger_node_id,
+            timestamp: start_time,
+            duration: (end_time - start_time).num_milliseconds(),
+            logs,
+            variables: ctx.variables(),
+        };
+        create_execution(execution).await;
+    }
+    run?;
+
+    // 发送完成信号
+    let _ = sse::send_string("[DONE]".to_string(), &sender);
+    Ok(result)
+}
+
+/// 工作流的节点与连接关系，流式节点的下游每条数据都从原始配置重新执行
+struct Graph {
+    nodes: Vec<Node>,
+    next_node_map: HashMap<NodeHandle, Vec<NodeHandle>>,
+}
+
+/// 从 start_nodes 开始逐层执行，直到没有后继节点；输出节点的结果写入 result
+async fn run_nodes(
+    graph: &Graph, mut start_nodes: Vec<String>, mut input_map: HashMap<String, Vec<(Option<String>, String)>>, ctx: &Context, sender: &Option<UnboundedSender<Result<Event, Infallible>>>,
+    logs: &mut Vec<Log>, result: &mut String,
+) -> anyhow::Result<()> {
+    let mut nodes = graph.nodes.clone();
     let mut node_outputs: HashMap<String, String> = HashMap::new(); // 存储节点执行结果
+    let mut node_handles: HashMap<String, Vec<String>> = HashMap::new(); // 存储节点选择的输出连接点
+    let mut streamed: HashSet<String> = HashSet::new(); // 下游已按条执行的流式节点
 
     loop {
         let mut has_more = false;
@@ -245,26 +364,40 @@ async fn run_workflow(workflow: Workflow, sender: Option<UnboundedSender<Result<
         // 执行当前层的所有节点
         for node_id in &start_nodes {
             if let Some(node) = nodes.iter_mut().find(|n| n.id == *node_id) {
+                let mut string_inputs: Vec<String> = vec![];
                 if let Some(inputs) = input_map.get_mut(node_id) {
                     // Sort inputs based on target_handle
                     inputs.sort_by(|a, b| a.0.cmp(&b.0));
-                    let string_inputs: Vec<String> = inputs.iter().map(|s| s.1.clone()).collect();
-                    node.reset_config(&string_inputs);
+                    string_inputs = inputs.iter().map(|s| s.1.clone()).collect();
                 }
-                match excute_node(node, &sender).await {
-                    Ok((node_logs, output)) => {
+                // 即使没有输入也需要渲染 ${vars.*} 占位符
+                if let Err(e) = node.reset_config(&string_inputs, ctx) {
+                    let _ = sse::send_error(format!("Node execution failed: {}", e), sender);
+                    return Err(e);
+                }
+                if stream::is_streaming(node) {
+                    run_stream(graph, node, ctx, sender, logs, result).await?;
+                    streamed.insert(node_id.clone());
+                    has_more = true;
+                    continue;
+                }
+                match excute_node(node, &string_inputs, ctx, sender).await {
+                    Ok((node_logs, output, handles)) => {
                         logs.extend(node_logs);
+                        ctx.set_output(node_id.clone(), output.clone());
 
                         // 存储节点输出结果
                         node_outputs.insert(node_id.clone(), output.clone());
+                        node_handles.insert(node_id.clone(), handles);
 
                         if node.kind == "output" {
-                            result = output;
+                            *result = output;
                         }
                     }
                     Err(e) => {
-                        let _ = sse::send_error(format!("Node execution failed: {}", e), &sender);
-                        return Err(e);
+                        let output = node_failed(graph, node, e, logs, sender)?;
+                        node_outputs.insert(node_id.clone(), output);
+                        node_handles.insert(node_id.clone(), vec![ERROR_HANDLE.to_string()]);
                     }
                 }
                 has_more = true;
@@ -275,22 +408,10 @@ async fn run_workflow(workflow: Workflow, sender: Option<UnboundedSender<Result<
         let mut next_nodes_map: HashMap<String, Vec<(Option<String>, String)>> = HashMap::new();
 
         for node_id in &start_nodes {
-            if let Some(node) = nodes.iter().find(|n| n.id == *node_id) {
+            if nodes.iter().any(|n| n.id == *node_id) && !streamed.contains(node_id) {
                 let output = node_outputs.get(node_id).cloned().unwrap_or_default();
-
-                if node.kind == "condition" {
-                    if let Some(targets) = next_node_map.get(&(node_id.clone(), Some(output.clone()))) {
-                        for (target_node, target_handle) in targets {
-                            next_nodes_map.entry(target_node.clone()).or_default().push((target_handle.clone(), output.clone()));
-                        }
-                    }
-                } else {
-                    if let Some(targets) = next_node_map.get(&(node_id.clone(), None)) {
-                        for (target_node, target_handle) in targets {
-                            next_nodes_map.entry(target_node.clone()).or_default().push((target_handle.clone(), output.clone()));
-                        }
-                    }
-                }
+                let handles = node_handles.get(node_id).cloned().unwrap_or_default();
+                route(graph, node_id, &output, &handles, &mut next_nodes_map);
             }
         }
 
@@ -303,53 +424,228 @@ async fn run_workflow(workflow: Workflow, sender: Option<UnboundedSender<Result<
         start_nodes = next_nodes;
 
         if !has_more || start_nodes.is_empty() {
-            break;
+            return Ok(());
         }
     }
+}
 
-    // 记录执行历史（仅当 record_execution 为 true）
-    if record_execution {
-        let end_time = Utc::now();
-        let execution = Execution {
-            id: Uuid::new_v4().to_string(),
-            status: "completed".to_string(),
-            workflow_id: workflow.id.unwrap_or_else(|| "unknown".to_string()),
-            input: HashMap::new(),
-            timestamp: start_time,
-            duration: (end_time - start_time).num_milliseconds(),
-            logs,
-        };
-        create_execution(execution).await;
+/// 节点失败时记录错误日志。连接了 error 连接点时返回沿该连接点继续的错误信息，否则结束执行
+fn node_failed(graph: &Graph, node: &Node, e: anyhow::Error, logs: &mut Vec<Log>, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<String> {
+    let log_data = LogData { kind: "error".to_string(), node_id: node.id.clone(), node_type: Some(node.kind.clone()), result: None, data: Some(e.to_string()) };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    if !graph.next_node_map.contains_key(&(node.id.clone(), Some(ERROR_HANDLE.to_string()))) {
+        let _ = sse::send_error(format!("Node execution failed: {}", e), sender);
+        return Err(e);
     }
+    sse::send_json(log_data, sender)?;
+    Ok(json!({ "nodeId": node.id, "error": e.to_string() }).to_string())
+}
 
-    // 发送完成信号
-    let _ = sse::send_string("[DONE]".to_string(), &sender);
+/// 把节点输出交给下游节点。条件、审批、失败等情况只沿节点选择的连接点继续，未选择时使用默认连接点
+fn route(graph: &Graph, node_id: &str, output: &str, handles: &[String], next_nodes_map: &mut HashMap<String, Vec<(Option<String>, String)>>) {
+    let handles: Vec<Option<String>> = if handles.is_empty() { vec![None] } else { handles.iter().cloned().map(Some).collect() };
+    for handle in handles {
+        if let Some(targets) = graph.next_node_map.get(&(node_id.to_string(), handle)) {
+            for (target_node, target_handle) in targets {
+                next_nodes_map.entry(target_node.clone()).or_default().push((target_handle.clone(), output.to_string()));
+            }
+        }
+    }
+}
+
+/// 以流式节点的一条输出（或错误信息）执行下游节点，返回输出节点的结果
+async fn run_item(
+    graph: &Graph, node: &Node, output: &str, handles: &[String], ctx: &Context, sender: &Option<UnboundedSender<Result<Event, Infallible>>>, logs: &mut Vec<Log>,
+) -> anyhow::Result<String> {
+    let mut input_map = HashMap::new();
+    route(graph, &node.id, output, handles, &mut input_map);
+    let mut result = String::new();
+    Box::pin(run_nodes(
+        graph,
+        input_map.keys().cloned().collect(),
+        input_map,
+        ctx,
+        sender,
+        logs,
+        &mut result,
+    ))
+    .await?;
     Ok(result)
 }
 
-async fn excute_node(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
+/// 多次执行输出节点的结果按行拼接
+fn append_result(result: &mut String, item_result: &str) {
+    if !item_result.is_empty() {
+        if !result.is_empty() {
+            result.push('\n');
+        }
+        result.push_str(item_result);
+    }
+}
+
+/// 执行流式节点：每条输出作为输入单独执行一次下游节点，多次执行输出节点的结果按行拼接。
+/// 节点失败时与普通节点一样沿 error 连接点继续。执行记录中只保留总条数与最后几条数据及下游日志的预览
+async fn run_stream(graph: &Graph, node: &Node, ctx: &Context, sender: &Option<UnboundedSender<Result<Event, Infallible>>>, logs: &mut Vec<Log>, result: &mut String) -> anyhow::Result<()> {
+    let mut items = match excute_stream(node, sender).await {
+        Ok((node_logs, items)) => {
+            logs.extend(node_logs);
+            items
+        }
+        Err(e) => {
+            let output = node_failed(graph, node, e, logs, sender)?;
+            *result = run_item(
+                graph,
+                node,
+                &output,
+                &[ERROR_HANDLE.to_string()],
+                ctx,
+                sender,
+                logs,
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let mut count = 0;
+    let mut total = 0;
+    let mut recent: VecDeque<Vec<Log>> = VecDeque::new();
+    let run: anyhow::Result<()> = async {
+        while let Some(item) = items.recv().await {
+            total += 1;
+            let mut item_logs = Vec::new();
+            let failed = item.is_err();
+            let run = match item {
+                Ok(item) => {
+                    count += 1;
+                    ctx.set_output(node.id.clone(), item.clone());
+                    let preview = stream::preview(&item);
+                    let log_data = LogData { kind: "output".to_string(), data: Some(preview.clone()), node_id: node.id.clone(), node_type: None, result: Some(preview) };
+                    item_logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+                    match sse::send_json(log_data, sender) {
+                        Ok(()) => run_item(graph, node, &item, &[], ctx, sender, &mut item_logs).await,
+                        Err(e) => Err(e),
+                    }
+                }
+                Err(e) => match node_failed(graph, node, e, &mut item_logs, sender) {
+                    Ok(output) => {
+                        run_item(
+                            graph,
+                            node,
+                            &output,
+                            &[ERROR_HANDLE.to_string()],
+                            ctx,
+                            sender,
+                            &mut item_logs,
+                        )
+                        .await
+                    }
+                    Err(e) => Err(e),
+                },
+            };
+            recent.push_back(item_logs.into_iter().map(stream::preview_log).collect());
+            if recent.len() > stream::LOG_ITEMS {
+                recent.pop_front();
+            }
+            append_result(result, &run?);
+            // 读取出错后流式节点不再产生输出
+            if failed {
+                break;
+            }
+        }
+        Ok(())
+    }
+    .await;
+
+    if total > recent.len() {
+        let log_data = LogData {
+            kind: "stream-info".to_string(),
+            data: Some(format!(
+                "共 {} 条数据，执行记录中只保留最后 {} 条的日志",
+                total,
+                recent.len()
+            )),
+            node_id: node.id.clone(),
+            node_type: None,
+            result: None,
+        };
+        logs.push(Log { timestamp: Utc::now(), data: log_data });
+    }
+    logs.extend(recent.into_iter().flatten());
+    run?;
+
+    let log_data = LogData { kind: "node_complete".to_string(), data: Some(format!("共输出 {} 条", count)), node_id: node.id.clone(), node_type: Some(node.kind.clone()), result: None };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    sse::send_json(log_data, sender)?;
+    Ok(())
+}
+
+/// 启动流式节点，返回 (日志, 逐条输出)，节点完成的日志在输出读取完后记录
+async fn excute_stream(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, stream::Items)> {
+    info!("Streaming node: {:?}", node);
+    let mut logs = vec![];
+    let log_data = LogData { kind: "node_start".to_string(), node_id: node.id.clone(), node_type: Some(node.kind.clone()), result: None, data: None };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    sse::send_json(log_data, sender)?;
+    let (node_logs, items) = match node.kind.as_str() {
+        "postgresql" => node::postgresql::stream(node, sender).await?,
+        "read-file" => node::read_file::stream(node, sender).await?,
+        other => return Err(anyhow!("{} 节点不支持流式输出", other)),
+    };
+    logs.extend(node_logs);
+    Ok((logs, items))
+}
+
+/// 执行单个节点，inputs 为上游节点的输出，返回 (日志, 输出, 选择的输出连接点)，未选择连接点时沿默认连接点继续
+async fn excute_node(node: &Node, inputs: &[String], ctx: &Context, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String, Vec<String>)> {
     info!("Executing node: {:?}", node);
     let mut logs = vec![];
     let log_data = LogData { kind: "node_start".to_string(), node_id: node.id.clone(), node_type: Some(node.kind.clone()), result: None, data: None };
     logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
     sse::send_json(log_data, sender)?;
+    let mut handles = vec![];
     let (node_logs, output) = match node.kind.as_str() {
         "input" => node::input::execute(node, sender).await?,
         "output" => node::output::execute(node, sender).await?,
         "ai-model" => node::llm::execute(node, sender).await?,
         "http-request" => node::http::execute(node, sender).await?,
-        "lua-script" => node::lua_script::execute(node, sender).await?,
+        "graphql" => node::graphql::execute(node, sender).await?,
+        "websocket" => node::websocket::execute(node, ctx, sender).await?,
+        "mqtt" => node::mqtt::execute(node, sender).await?,
+        "lua-script" => node::lua_script::execute(node, inputs, ctx, sender).await?,
         "postgresql" => node::postgresql::execute(node, sender).await?,
+        "mysql" => node::mysql::execute(node, sender).await?,
+        "sqlite" => node::sqlite::execute(node, sender).await?,
         "condition" => node::condition::execute(node, sender).await?,
         "read-file" => node::read_file::execute(node, sender).await?,
         "write-file" => node::write_file::execute(node, sender).await?,
+        "transform" => node::transform::execute(node, ctx, sender).await?,
+        "convert" => node::convert::execute(node, sender).await?,
+        "email" => node::email::execute(node, sender).await?,
+        "redis" => node::redis::execute(node, sender).await?,
+        "set-variables" => node::set_variables::execute(node, ctx, sender).await?,
+        kind if trigger::is_trigger(kind) => node::trigger::execute(node, ctx, sender).await?,
+        "approval" => {
+            let (node_logs, output, decision) = node::approval::execute(node, ctx, sender).await?;
+            handles.push(decision);
+            (node_logs, output)
+        }
+        "exec" => {
+            let (node_logs, output, status) = node::exec::execute(node, sender).await?;
+            handles.push(status);
+            handles.push(EXEC_ALWAYS_HANDLE.to_string());
+            (node_logs, output)
+        }
         _ => (vec![], "".to_string()),
     };
+    if node.kind == "condition" {
+        handles.push(output.clone());
+    }
     logs.extend(node_logs);
     let log_data = LogData { kind: "node_complete".to_string(), data: None, node_id: node.id.clone(), node_type: Some(node.kind.clone()), result: None };
     logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
     sse::send_json(log_data, sender).unwrap();
-    Ok((logs, output))
+    Ok((logs, output, handles))
 }
 
 fn sse_response(receiver: UnboundedReceiver<Result<Event, Infallible>>) -> impl IntoResponse {
@@ -377,3 +673,81 @@ fn sse_response(receiver: UnboundedReceiver<Result<Event, Infallible>>) -> impl
 //     // 返回 (HeaderMap, Sse)
 //     (headers, sse).into_response()
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, kind: &str, config: Value) -> Node {
+        serde_json::from_value(json!({ "id": id, "type": kind, "position": { "x": 0, "y": 0 }, "config": config })).unwrap()
+    }
+
+    /// read-file 逐行读取 path，每行输出到 ok，失败时沿 error 连接点输出到 failed（with_error 为 true 时）
+    async fn run_stream_file(path: &str, with_error: bool) -> (anyhow::Result<()>, Vec<Log>, String) {
+        let mut nodes = vec![node("r", "read-file", json!({ "path": path, "stream": "true" })), node("ok", "output", json!({ "output": "ok ${input}" }))];
+        let mut next_node_map = HashMap::from([(("r".to_string(), None), vec![("ok".to_string(), None)])]);
+        if with_error {
+            nodes.push(node("failed", "output", json!({ "output": "${input}" })));
+            next_node_map.insert(("r".to_string(), Some(ERROR_HANDLE.to_string())), vec![(
+                "failed".to_string(),
+                None,
+            )]);
+        }
+        let graph = Graph { nodes, next_node_map };
+        let ctx = Context::new("test".to_string(), HashMap::new());
+        let (mut logs, mut result) = (Vec::new(), String::new());
+        let run = run_nodes(
+            &graph,
+            vec!["r".to_string()],
+            HashMap::new(),
+            &ctx,
+            &None,
+            &mut logs,
+            &mut result,
+        )
+        .await;
+        (run, logs, result)
+    }
+
+    fn write_lines(name: &str, content: &[u8]) -> String {
+        let path = format!("target/{}", name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn routes_stream_failures_to_error_handle() {
+        let path = write_lines("stream-invalid.txt", b"a\nb\n\xff\n");
+        let (run, _, result) = run_stream_file(&path, true).await;
+        run.unwrap();
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(lines[..2], ["ok a", "ok b"]);
+        let error: Value = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(error["nodeId"], "r");
+        assert!(error["error"].as_str().unwrap().starts_with("读取文件"));
+
+        let (run, logs, _) = run_stream_file(&path, false).await;
+        assert!(run.unwrap_err().to_string().starts_with("读取文件"));
+        assert!(logs.iter().any(|log| log.data.node_id == "ok"));
+        assert!(logs.iter().any(|log| log.data.kind == "error" && log.data.node_id == "r"));
+
+        let (run, _, result) = run_stream_file("target/stream-missing.txt", true).await;
+        run.unwrap();
+        assert!(serde_json::from_str::<Value>(&result).unwrap()["error"].as_str().unwrap().starts_with("打开文件"));
+        assert!(run_stream_file("target/stream-missing.txt", false).await.0.is_err());
+    }
+
+    #[tokio::test]
+    async fn keeps_only_recent_stream_logs() {
+        let content: String = (1..=50).map(|i| format!("{}\n", i)).collect();
+        let path = write_lines("stream-lines.txt", content.as_bytes());
+        let (run, logs, result) = run_stream_file(&path, false).await;
+        run.unwrap();
+        assert_eq!(result.lines().count(), 50);
+        let outputs: Vec<&str> = logs.iter().filter(|log| log.data.kind == "output" && log.data.node_id == "r").map(|log| log.data.data.as_deref().unwrap()).collect();
+        assert_eq!(outputs.len(), stream::LOG_ITEMS);
+        assert_eq!(outputs.last(), Some(&"50"));
+        assert!(logs.iter().any(|log| log.data.data.as_deref() == Some("共 50 条数据，执行记录中只保留最后 20 条的日志")));
+        assert!(logs.iter().any(|log| log.data.kind == "node_complete" && log.data.data.as_deref() == Some("共输出 50 条")));
+    }
+}
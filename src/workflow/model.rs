@@ -1,7 +1,11 @@
 use std::collections::HashMap;
 
+use anyhow::anyhow;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{context::Context, parameter::WorkflowParameter};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Position {
@@ -19,25 +23,135 @@ pub struct Node {
     pub label: Option<String>,
 }
 
+/// SQL 节点：查询语句默认不允许文本替换，输入通过 params 绑定为查询参数
+const SQL_NODES: [&str; 3] = ["postgresql", "mysql", "sqlite"];
+
 impl Node {
-    pub fn reset_config(&mut self, inputs: &Vec<String>) {
-        if inputs.is_empty() {
-            return;
+    /// 渲染配置中的占位符：${input}、${input_N}、${vars.name} 以及 ${params.name}。
+    /// SQL 节点的 query（包括 statements 中每条语句的 query）出现可替换的占位符时报错，除非设置 rawInterpolation=true；
+    /// lua-script 的 script 同样不允许替换，除非设置 rawInterpolation=true；
+    /// params、statements 等 JSON 配置逐个渲染字符串值，替换内容不会破坏 JSON 结构
+    pub fn reset_config(&mut self, inputs: &[String], ctx: &Context) -> anyhow::Result<()> {
+        let variables = ctx.variables();
+        let is_sql = SQL_NODES.contains(&self.kind.as_str());
+        let raw_interpolation = self.config.get("rawInterpolation").is_some_and(|v| v.trim() == "true");
+        let guard_sql = is_sql && !raw_interpolation;
+        let guard_lua = self.kind == "lua-script" && !raw_interpolation;
+        for (key, value) in self.config.iter_mut() {
+            if is_json_config(&self.kind, key)
+                && let Ok(mut json) = serde_json::from_str::<Value>(value)
+            {
+                render_json(&mut json, guard_sql, inputs, &variables, &ctx.params)?;
+                *value = json.to_string();
+                continue;
+            }
+            let rendered = render_template(value, inputs, &variables, &ctx.params);
+            if guard_sql && key == "query" && rendered != *value {
+                return Err(raw_sql_error());
+            }
+            if guard_lua && key == "script" && rendered != *value {
+                return Err(anyhow!(
+                    "Lua 脚本中不允许直接插入占位符，请通过 input、inputs、nodes、vars、params 等全局变量读取数据，或设置 rawInterpolation=true"
+                ));
+            }
+            *value = rendered;
         }
-        for (_key, value) in self.config.iter_mut() {
-            let mut new_value = value.clone();
-            if !inputs.is_empty() {
-                new_value = new_value.replace("${input}", &inputs[0]);
+        Ok(())
+    }
+}
+
+/// 值为 JSON 的配置项按 JSON 渲染：SQL 节点的 params、statements，http-request 的 fields 以及 graphql 的 variables
+fn is_json_config(kind: &str, key: &str) -> bool {
+    match kind {
+        "http-request" => key == "fields",
+        "graphql" => key == "variables",
+        "websocket" => key == "messages",
+        kind if SQL_NODES.contains(&kind) => key == "params" || key == "statements",
+        _ => false,
+    }
+}
+
+fn raw_sql_error() -> anyhow::Error {
+    anyhow!("SQL 查询中不允许直接插入占位符，请使用 $1、$2 等参数并在 params 中绑定，或设置 rawInterpolation=true")
+}
+
+/// 渲染 JSON 中的所有字符串值，guard_sql 为 true 时对象中的 query 字段不允许替换
+fn render_json(value: &mut Value, guard_sql: bool, inputs: &[String], variables: &HashMap<String, Value>, params: &HashMap<String, String>) -> anyhow::Result<()> {
+    match value {
+        Value::String(s) => *s = render_template(s, inputs, variables, params),
+        Value::Array(items) => {
+            for item in items {
+                render_json(item, guard_sql, inputs, variables, params)?;
             }
-            if inputs.len() > 1 {
-                for i in 1..inputs.len() {
-                    let placeholder = format!("${{input_{}}}", i);
-                    new_value = new_value.replace(&placeholder, &inputs[i]);
+        }
+        Value::Object(map) => {
+            for (key, item) in map {
+                if guard_sql
+                    && key == "query"
+                    && let Value::String(query) = item
+                    && render_template(query, inputs, variables, params) != *query
+                {
+                    return Err(raw_sql_error());
                 }
+                render_json(item, guard_sql, inputs, variables, params)?;
             }
-            *value = new_value;
         }
+        _ => {}
     }
+    Ok(())
+}
+
+/// 单次扫描替换占位符，替换进来的内容不会被再次展开；无法解析的占位符原样保留
+fn render_template(template: &str, inputs: &[String], variables: &HashMap<String, Value>, params: &HashMap<String, String>) -> String {
+    let mut result = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        let placeholder = &rest[start..start + end + 1];
+        match resolve_placeholder(
+            &placeholder[2..placeholder.len() - 1],
+            inputs,
+            variables,
+            params,
+        ) {
+            Some(value) => result.push_str(&value),
+            None => result.push_str(placeholder),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn resolve_placeholder(key: &str, inputs: &[String], variables: &HashMap<String, Value>, params: &HashMap<String, String>) -> Option<String> {
+    if key == "input" {
+        return inputs.first().cloned();
+    }
+    if let Some(index) = key.strip_prefix("input_") {
+        return index.parse::<usize>().ok().filter(|i| *i > 0).and_then(|i| inputs.get(i)).cloned();
+    }
+    if let Some(name) = key.strip_prefix("params.") {
+        return params.get(name).cloned();
+    }
+    if let Some(path) = key.strip_prefix("vars.") {
+        // 支持 ${vars.user.name} 形式访问嵌套字段
+        let mut parts = path.split('.');
+        let mut value = variables.get(parts.next()?)?;
+        for part in parts {
+            value = match value {
+                Value::Array(items) => items.get(part.parse::<usize>().ok()?)?,
+                _ => value.get(part)?,
+            };
+        }
+        return Some(match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        });
+    }
+    None
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -56,6 +170,8 @@ pub struct Workflow {
     pub name: String,
     pub nodes: Vec<Node>,
     pub edges: Vec<Edge>,
+    #[serde(default)]
+    pub parameters: Vec<WorkflowParameter>,
     #[serde(rename = "createdAt")]
     pub created_at: Option<DateTime<Utc>>,
     #[serde(rename = "updatedAt")]
@@ -70,10 +186,16 @@ pub struct Execution {
     #[serde(rename = "workflowId")]
     pub workflow_id: String,
     pub input: HashMap<String, String>,
+    /// 由触发器启动时为触发器节点 id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger: Option<String>,
     pub logs: Vec<Log>,
     pub duration: i64,
     pub status: String,
     pub timestamp: DateTime<Utc>,
+    /// 执行结束时的变量快照
+    #[serde(default)]
+    pub variables: HashMap<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -96,7 +218,154 @@ pub struct LogData {
 
 /// 执行请求
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 pub struct WorkflowReqParam {
     pub input: Option<String>,
+    /// 工作流参数的值，按 Workflow.parameters 校验
+    #[serde(flatten)]
+    pub params: HashMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn render(template: &str) -> String {
+        let inputs = ["first".to_string(), "second".to_string()];
+        let variables = HashMap::from([
+            (
+                "user".to_string(),
+                json!({ "name": "张三", "tags": ["a", "b"], "age": 30 }),
+            ),
+            ("raw".to_string(), json!("${input}")),
+        ]);
+        let params = HashMap::from([("limit".to_string(), "10".to_string())]);
+        render_template(template, &inputs, &variables, &params)
+    }
+
+    #[test]
+    fn replaces_inputs_params_and_variables() {
+        assert_eq!(
+            render("${input}/${input_1}/${params.limit}"),
+            "first/second/10"
+        );
+        assert_eq!(
+            render("${vars.user.name} ${vars.user.tags.1} ${vars.user.age}"),
+            "张三 b 30"
+        );
+        assert_eq!(render("${vars.user.tags}"), r#"["a","b"]"#);
+    }
+
+    #[test]
+    fn keeps_unresolved_placeholders() {
+        assert_eq!(
+            render("${input_0} ${input_5} ${vars.user.missing} ${unknown}"),
+            "${input_0} ${input_5} ${vars.user.missing} ${unknown}"
+        );
+        assert_eq!(render("a ${input"), "a ${input");
+    }
+
+    #[test]
+    fn does_not_expand_substituted_values() {
+        assert_eq!(render("${vars.raw}"), "${input}");
+    }
+
+    fn node(kind: &str, config: Value) -> Node {
+        serde_json::from_value(json!({ "id": "n", "type": kind, "position": { "x": 0, "y": 0 }, "config": config })).unwrap()
+    }
+
+    fn reset(mut node: Node) -> anyhow::Result<Node> {
+        let ctx = Context::new(
+            "w".to_string(),
+            HashMap::from([("id".to_string(), "7".to_string())]),
+        );
+        node.reset_config(&["1; drop table users".to_string()], &ctx)?;
+        Ok(node)
+    }
+
+    #[test]
+    fn refuses_placeholders_in_sql_query() {
+        for kind in SQL_NODES {
+            let error = reset(node(
+                kind,
+                json!({ "query": "select * from users where id = ${input}" }),
+            ))
+            .unwrap_err();
+            assert!(error.to_string().starts_with("SQL 查询中不允许直接插入占位符"));
+            assert!(reset(node(kind, json!({ "query": "select ${params.id}" }))).is_err());
+        }
+    }
+
+    #[test]
+    fn raw_interpolation_allows_placeholders() {
+        let node = reset(node(
+            "postgresql",
+            json!({ "query": "select ${input}", "rawInterpolation": "true" }),
+        ))
+        .unwrap();
+        assert_eq!(node.config["query"], "select 1; drop table users");
+    }
+
+    #[test]
+    fn refuses_placeholders_in_statement_queries() {
+        let statements = r#"[{ "query": "insert into logs values ($1)", "params": ["${input}"] }, { "query": "delete from users where id = ${params.id}" }]"#;
+        let error = reset(node("postgresql", json!({ "statements": statements }))).unwrap_err();
+        assert!(error.to_string().starts_with("SQL 查询中不允许直接插入占位符"));
+
+        let node = reset(node(
+            "postgresql",
+            json!({ "statements": statements, "rawInterpolation": "true" }),
+        ))
+        .unwrap();
+        let statements: Value = serde_json::from_str(&node.config["statements"]).unwrap();
+        assert_eq!(statements[0]["params"], json!(["1; drop table users"]));
+        assert_eq!(statements[1]["query"], "delete from users where id = 7");
+    }
+
+    #[test]
+    fn binds_params_without_touching_query() {
+        let node = reset(node(
+            "postgresql",
+            json!({ "query": "select * from users where id = $1 and name = $2", "params": r#"["${params.id}", "${input}"]"# }),
+        ))
+        .unwrap();
+        assert_eq!(
+            node.config["query"],
+            "select * from users where id = $1 and name = $2"
+        );
+        assert_eq!(
+            serde_json::from_str::<Value>(&node.config["params"]).unwrap(),
+            json!(["7", "1; drop table users"])
+        );
+    }
+
+    #[test]
+    fn other_nodes_interpolate() {
+        let node = reset(node(
+            "http-request",
+            json!({ "url": "http://example.com/${params.id}" }),
+        ))
+        .unwrap();
+        assert_eq!(node.config["url"], "http://example.com/7");
+    }
+
+    #[test]
+    fn refuses_placeholders_in_lua_scripts_by_default() {
+        let error = reset(node("lua-script", json!({ "script": "return ${input}" }))).unwrap_err();
+        assert!(
+            error.to_string().starts_with("Lua 脚本中不允许直接插入占位符"),
+            "{}",
+            error
+        );
+        let raw = reset(node(
+            "lua-script",
+            json!({ "script": "return '${input}'", "rawInterpolation": "true" }),
+        ))
+        .unwrap();
+        assert_eq!(raw.config["script"], "return '1; drop table users'");
+        let plain = reset(node("lua-script", json!({ "script": "return input" }))).unwrap();
+        assert_eq!(plain.config["script"], "return input");
+    }
 }
@@ -0,0 +1,58 @@
+use std::{convert::Infallible, time::Duration};
+
+use anyhow::anyhow;
+use axum::response::sse::Event;
+use chrono::Utc;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::super::{
+    approval::{self, Approval}, context::Context, model::{Log, LogData, Node}, sse
+};
+
+/// 未配置 timeout 时的审批期限（秒）
+const DEFAULT_TIMEOUT_SECS: u64 = 24 * 60 * 60;
+
+/// 挂起执行等待人工审批，返回 (日志, 输出, 连接点)，连接点为 approved、rejected 或 timeout。
+/// timeout 为等待的秒数，默认 24 小时，0 表示一直等待
+pub async fn execute(node: &Node, ctx: &Context, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String, String)> {
+    let mut logs = vec![];
+    let message = node.config.get("message").cloned().unwrap_or_default();
+    let payload = node.config.get("payload").cloned().unwrap_or_default();
+    let timeout = match node.config.get("timeout").map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        Some(v) => v.parse::<u64>().map_err(|_| anyhow!("timeout 不是合法的秒数: {}", v))?,
+        None => DEFAULT_TIMEOUT_SECS,
+    };
+    let timeout = (timeout > 0).then(|| Duration::from_secs(timeout));
+
+    let approval = Approval {
+        id: uuid::Uuid::new_v4().to_string(),
+        workflow_id: ctx.workflow_id.clone(),
+        execution_id: ctx.execution_id.clone(),
+        node_id: node.id.clone(),
+        message,
+        payload: payload.clone(),
+        created_at: Utc::now(),
+    };
+
+    let log_data = LogData { kind: "approval_pending".to_string(), node_id: node.id.clone(), node_type: Some(node.kind.clone()), result: None, data: Some(serde_json::to_string(&approval)?) };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    sse::send_json(log_data, sender)?;
+
+    let (output, handle, data) = match approval::wait_for_decision(approval, timeout).await {
+        Some(decision) => {
+            let handle = if decision.approved { "approved" } else { "rejected" };
+            (
+                decision.payload.clone(),
+                handle,
+                serde_json::to_string(&decision)?,
+            )
+        }
+        None => (payload, "timeout", "审批超时".to_string()),
+    };
+
+    let log_data = LogData { kind: "approval_decision".to_string(), node_id: node.id.clone(), node_type: Some(node.kind.clone()), result: Some(handle.to_string()), data: Some(data) };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    sse::send_json(log_data, sender)?;
+
+    Ok((logs, output, handle.to_string()))
+}
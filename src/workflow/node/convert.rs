@@ -0,0 +1,379 @@
+use std::convert::Infallible;
+
+use anyhow::{anyhow, bail};
+use axum::response::sse::Event;
+use chrono::Utc;
+use csv::{QuoteStyle, ReaderBuilder, WriterBuilder};
+use quick_xml::{
+    Reader, Writer, events::{BytesDecl, BytesStart, BytesText, Event as XmlEvent}
+};
+use serde_json::{Map, Value};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::super::{
+    binary::Binary, model::{Log, LogData, Node}, sse
+};
+
+/// XML 属性与文本在 JSON 中的键
+const ATTRIBUTE_PREFIX: &str = "@";
+const TEXT_KEY: &str = "#text";
+
+/// CSV 选项：delimiter（默认逗号，\t 表示制表符）、quote（默认双引号）、header（首行为列名，默认 true）、quoteAll（所有字段加引号）
+struct CsvOptions {
+    delimiter: u8,
+    quote: u8,
+    header: bool,
+    quote_all: bool,
+}
+
+impl CsvOptions {
+    fn new(node: &Node) -> anyhow::Result<Self> {
+        let char_option = |key: &str, default: u8| -> anyhow::Result<u8> {
+            match node.config.get(key).map(|v| v.as_str()).filter(|v| !v.is_empty()) {
+                None => Ok(default),
+                Some("\\t") | Some("tab") => Ok(b'\t'),
+                Some(v) if v.len() == 1 => Ok(v.as_bytes()[0]),
+                Some(v) => Err(anyhow!("{} 必须是单个 ASCII 字符: {}", key, v)),
+            }
+        };
+        Ok(CsvOptions {
+            delimiter: char_option("delimiter", b',')?,
+            quote: char_option("quote", b'"')?,
+            header: node.config.get("header").is_none_or(|v| v.trim() != "false"),
+            quote_all: node.config.get("quoteAll").is_some_and(|v| v.trim() == "true"),
+        })
+    }
+}
+
+/// 在 JSON、CSV、XML、YAML 之间转换：from 为输入格式，to 为输出格式，默认 JSON
+pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
+    let format = |key: &str| node.config.get(key).map(|v| v.trim().to_lowercase()).filter(|v| !v.is_empty()).unwrap_or("json".to_string());
+    let (from, to) = (format("from"), format("to"));
+    let input = node.config.get("input").cloned().unwrap_or_default();
+    // 输入为 read-file 等节点输出的二进制引用时读取其内容
+    let input = match Binary::parse(&input) {
+        Some(binary) => String::from_utf8_lossy(&binary.read()?).to_string(),
+        None => input,
+    };
+
+    let value = parse(&from, &input, node).map_err(|e| anyhow!("解析 {} 失败: {}", from, e))?;
+    let output = serialize(&to, &value, node).map_err(|e| anyhow!("转换为 {} 失败: {}", to, e))?;
+
+    let log_data = LogData { kind: "output".to_string(), data: Some(output.clone()), node_id: node.id.clone(), node_type: None, result: Some(output.clone()) };
+    sse::send_json(log_data.clone(), sender)?;
+
+    Ok((vec![Log { timestamp: Utc::now(), data: log_data }], output))
+}
+
+fn parse(format: &str, input: &str, node: &Node) -> anyhow::Result<Value> {
+    match format {
+        "json" => Ok(serde_json::from_str(input)?),
+        "csv" => csv_to_json(input, &CsvOptions::new(node)?),
+        "xml" => xml_to_json(input),
+        "yaml" | "yml" => Ok(serde_yaml_ng::from_str(input)?),
+        other => bail!("不支持的格式 {}", other),
+    }
+}
+
+fn serialize(format: &str, value: &Value, node: &Node) -> anyhow::Result<String> {
+    match format {
+        "json" => Ok(value.to_string()),
+        "csv" => json_to_csv(value, &CsvOptions::new(node)?),
+        "xml" => json_to_xml(
+            value,
+            node.config.get("rootName").map(|v| v.trim()).filter(|v| !v.is_empty()).unwrap_or("root"),
+        ),
+        "yaml" | "yml" => Ok(serde_yaml_ng::to_string(value)?),
+        other => bail!("不支持的格式 {}", other),
+    }
+}
+
+/// 有列名时每行转换为对象，否则为字符串数组
+fn csv_to_json(input: &str, options: &CsvOptions) -> anyhow::Result<Value> {
+    let mut reader = ReaderBuilder::new().delimiter(options.delimiter).quote(options.quote).has_headers(options.header).flexible(true).from_reader(input.as_bytes());
+    let headers = if options.header { Some(reader.headers()?.clone()) } else { None };
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let row = match &headers {
+            Some(headers) => Value::Object(headers.iter().zip(record.iter()).map(|(name, value)| (name.to_string(), Value::String(value.to_string()))).collect()),
+            None => Value::Array(record.iter().map(|value| Value::String(value.to_string())).collect()),
+        };
+        rows.push(row);
+    }
+    Ok(Value::Array(rows))
+}
+
+/// 输入为对象数组时按出现顺序合并所有键作为列，为数组的数组时逐行输出；嵌套的值以 JSON 字符串写入
+fn json_to_csv(value: &Value, options: &CsvOptions) -> anyhow::Result<String> {
+    let rows = match value {
+        Value::Array(rows) => rows.clone(),
+        other => vec![other.clone()],
+    };
+    let mut writer = WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .quote(options.quote)
+        .quote_style(if options.quote_all { QuoteStyle::Always } else { QuoteStyle::Necessary })
+        .flexible(true)
+        .from_writer(Vec::new());
+    let cell = |value: &Value| match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+
+    if rows.iter().all(|row| row.is_object()) {
+        let mut columns: Vec<String> = Vec::new();
+        for row in &rows {
+            for key in row.as_object().into_iter().flat_map(|row| row.keys()) {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
+        if options.header {
+            writer.write_record(&columns)?;
+        }
+        for row in &rows {
+            writer.write_record(columns.iter().map(|column| row.get(column).map(cell).unwrap_or_default()))?;
+        }
+    } else {
+        for row in &rows {
+            match row {
+                Value::Array(cells) => writer.write_record(cells.iter().map(cell))?,
+                other => writer.write_record([cell(other)])?,
+            }
+        }
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// 元素转换为对象：属性以 @ 开头，文本为 #text，同名子元素合并为数组，只有文本的元素为字符串，空元素为 null
+fn xml_to_json(input: &str) -> anyhow::Result<Value> {
+    let mut reader = Reader::from_str(input);
+    reader.config_mut().trim_text(true);
+    // 正在解析的元素：名称、属性与子元素、文本
+    let mut stack: Vec<(String, Map<String, Value>, String)> = vec![(String::new(), Map::new(), String::new())];
+    loop {
+        match reader.read_event()? {
+            XmlEvent::Start(e) => {
+                let (name, attributes) = element(&e)?;
+                stack.push((name, attributes, String::new()));
+            }
+            XmlEvent::Empty(e) => {
+                let (name, attributes) = element(&e)?;
+                let parent = stack.last_mut().ok_or_else(|| anyhow!("XML 结构错误"))?;
+                insert(&mut parent.1, name, finish(attributes, String::new()));
+            }
+            XmlEvent::Text(e) => {
+                let parent = stack.last_mut().ok_or_else(|| anyhow!("XML 结构错误"))?;
+                parent.2.push_str(&e.unescape()?);
+            }
+            XmlEvent::CData(e) => {
+                let parent = stack.last_mut().ok_or_else(|| anyhow!("XML 结构错误"))?;
+                parent.2.push_str(&String::from_utf8_lossy(&e));
+            }
+            XmlEvent::End(_) => {
+                let (name, children, text) = stack.pop().ok_or_else(|| anyhow!("XML 结构错误"))?;
+                let parent = stack.last_mut().ok_or_else(|| anyhow!("多余的结束标签 {}", name))?;
+                insert(&mut parent.1, name, finish(children, text));
+            }
+            XmlEvent::Eof => break,
+            // 声明、注释、处理指令、DOCTYPE
+            _ => {}
+        }
+    }
+    match stack.pop() {
+        Some((_, document, _)) if stack.is_empty() => Ok(Value::Object(document)),
+        _ => bail!("XML 元素未闭合"),
+    }
+}
+
+fn element(start: &BytesStart) -> anyhow::Result<(String, Map<String, Value>)> {
+    let name = String::from_utf8_lossy(start.name().as_ref()).to_string();
+    let mut attributes = Map::new();
+    for attribute in start.attributes() {
+        let attribute = attribute?;
+        let key = format!(
+            "{}{}",
+            ATTRIBUTE_PREFIX,
+            String::from_utf8_lossy(attribute.key.as_ref())
+        );
+        attributes.insert(key, Value::String(attribute.unescape_value()?.to_string()));
+    }
+    Ok((name, attributes))
+}
+
+fn finish(mut children: Map<String, Value>, text: String) -> Value {
+    match (children.is_empty(), text.is_empty()) {
+        (true, true) => Value::Null,
+        (true, false) => Value::String(text),
+        (false, empty) => {
+            if !empty {
+                children.insert(TEXT_KEY.to_string(), Value::String(text));
+            }
+            Value::Object(children)
+        }
+    }
+}
+
+fn insert(map: &mut Map<String, Value>, key: String, value: Value) {
+    match map.get_mut(&key) {
+        Some(Value::Array(items)) => items.push(value),
+        Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+        None => {
+            map.insert(key, value);
+        }
+    }
+}
+
+/// xml_to_json 的逆转换。只有一个键的对象以该键为根元素，否则使用 rootName；顶层数组的元素写为 item
+fn json_to_xml(value: &Value, root: &str) -> anyhow::Result<String> {
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(XmlEvent::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    match value {
+        Value::Object(map) if map.len() == 1 && map.keys().all(|key| !key.starts_with(ATTRIBUTE_PREFIX) && key != TEXT_KEY) => {
+            for (name, value) in map {
+                write_element(&mut writer, name, value)?;
+            }
+        }
+        Value::Array(_) => {
+            writer.write_event(XmlEvent::Start(BytesStart::new(root)))?;
+            write_element(&mut writer, "item", value)?;
+            writer.write_event(XmlEvent::End(BytesStart::new(root).to_end()))?;
+        }
+        other => write_element(&mut writer, root, other)?,
+    }
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+fn write_element(writer: &mut Writer<Vec<u8>>, name: &str, value: &Value) -> anyhow::Result<()> {
+    let text = |value: &Value| match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    match value {
+        // 数组写为多个同名元素
+        Value::Array(items) => {
+            for item in items {
+                write_element(writer, name, item)?;
+            }
+        }
+        Value::Object(map) => {
+            let mut start = BytesStart::new(name);
+            for (key, value) in map {
+                if let Some(attribute) = key.strip_prefix(ATTRIBUTE_PREFIX) {
+                    start.push_attribute((attribute, text(value).as_str()));
+                }
+            }
+            let children: Vec<(&String, &Value)> = map.iter().filter(|(key, _)| !key.starts_with(ATTRIBUTE_PREFIX)).collect();
+            if children.is_empty() {
+                writer.write_event(XmlEvent::Empty(start))?;
+                return Ok(());
+            }
+            writer.write_event(XmlEvent::Start(start.borrow()))?;
+            for (key, value) in children {
+                if key == TEXT_KEY {
+                    writer.write_event(XmlEvent::Text(BytesText::new(&text(value))))?;
+                } else {
+                    write_element(writer, key, value)?;
+                }
+            }
+            writer.write_event(XmlEvent::End(start.to_end()))?;
+        }
+        Value::Null => writer.write_event(XmlEvent::Empty(BytesStart::new(name)))?,
+        scalar => {
+            writer.write_event(XmlEvent::Start(BytesStart::new(name)))?;
+            writer.write_event(XmlEvent::Text(BytesText::new(&text(scalar))))?;
+            writer.write_event(XmlEvent::End(BytesStart::new(name).to_end()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const DEFAULT_CSV: CsvOptions = CsvOptions { delimiter: b',', quote: b'"', header: true, quote_all: false };
+
+    #[test]
+    fn csv_round_trip() {
+        let rows = json!([
+            { "name": "张三", "note": "a, \"b\"\nc" },
+            { "name": "李四", "note": "" },
+        ]);
+        let text = json_to_csv(&rows, &DEFAULT_CSV).unwrap();
+        assert!(text.starts_with("name,note\n"));
+        assert_eq!(csv_to_json(&text, &DEFAULT_CSV).unwrap(), rows);
+
+        let options = CsvOptions { delimiter: b'\t', quote_all: true, ..DEFAULT_CSV };
+        let text = json_to_csv(&rows, &options).unwrap();
+        assert!(text.starts_with("\"name\"\t\"note\"\n"));
+        assert_eq!(csv_to_json(&text, &options).unwrap(), rows);
+    }
+
+    #[test]
+    fn csv_merges_columns_and_writes_nested_values_as_json() {
+        let text = json_to_csv(
+            &json!([{ "a": 1 }, { "b": { "c": [1, 2] }, "a": null }]),
+            &DEFAULT_CSV,
+        )
+        .unwrap();
+        assert_eq!(text, "a,b\n1,\n,\"{\"\"c\"\":[1,2]}\"\n");
+    }
+
+    #[test]
+    fn csv_without_header() {
+        let options = CsvOptions { delimiter: b';', header: false, ..DEFAULT_CSV };
+        let rows = json!([["1", "x"], ["2", "y", "extra"]]);
+        let text = json_to_csv(&rows, &options).unwrap();
+        assert_eq!(text, "1;x\n2;y;extra\n");
+        assert_eq!(csv_to_json(&text, &options).unwrap(), rows);
+    }
+
+    #[test]
+    fn xml_round_trip() {
+        let document = json!({
+            "order": {
+                "@id": "42",
+                "customer": "A & B <co>",
+                "item": [
+                    { "@sku": "x1", "#text": "苹果" },
+                    { "@sku": "x2", "#text": "梨" },
+                ],
+                "note": null,
+            }
+        });
+        let text = json_to_xml(&document, "root").unwrap();
+        assert!(text.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+        assert!(text.contains("A &amp; B &lt;co&gt;"));
+        assert_eq!(xml_to_json(&text).unwrap(), document);
+    }
+
+    #[test]
+    fn xml_wraps_arrays_and_scalars_in_root() {
+        let text = json_to_xml(&json!(["a", "b"]), "list").unwrap();
+        assert_eq!(
+            xml_to_json(&text).unwrap(),
+            json!({ "list": { "item": ["a", "b"] } })
+        );
+        let text = json_to_xml(&json!({ "a": 1, "b": true }), "list").unwrap();
+        assert_eq!(
+            xml_to_json(&text).unwrap(),
+            json!({ "list": { "a": "1", "b": "true" } })
+        );
+    }
+
+    #[test]
+    fn xml_reads_cdata_and_rejects_unclosed_elements() {
+        assert_eq!(
+            xml_to_json("<a><![CDATA[<b>]]></a>").unwrap(),
+            json!({ "a": "<b>" })
+        );
+        assert!(xml_to_json("<a><b></b>").is_err());
+        assert!(xml_to_json("<a></a></b>").is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use std::{convert::Infallible, path::Path, time::Duration};
+
+use anyhow::anyhow;
+use axum::response::sse::Event;
+use chrono::Utc;
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor, message::{Attachment, Mailbox, MultiPart, SinglePart, header::ContentType}, transport::smtp::{
+        authentication::Credentials, client::{Tls, TlsParameters}
+    }
+};
+use serde_json::{Value, json};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::super::{
+    binary::Binary, model::{Log, LogData, Node}, sse
+};
+
+/// 通过 SMTP 发送邮件，输出包含 messageId 与服务器响应的 JSON
+pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
+    let mut logs = vec![];
+    let host = node.config.get("host").map(|v| v.trim()).filter(|v| !v.is_empty()).ok_or_else(|| anyhow!("SMTP host 为空"))?;
+    // starttls（默认）、tls（隐式 TLS）或 none（明文，适用于本地测试服务器）
+    let security = node.config.get("security").map(|v| v.trim().to_lowercase()).unwrap_or("starttls".to_string());
+    let default_port = match security.as_str() {
+        "tls" => 465,
+        "none" => 25,
+        _ => 587,
+    };
+    let port = node.config.get("port").and_then(|v| v.trim().parse::<u16>().ok()).unwrap_or(default_port);
+    let username = node.config.get("username").map(|v| v.trim()).filter(|v| !v.is_empty());
+    let password = node.config.get("password").cloned().unwrap_or_default();
+    let timeout = node.config.get("timeout").and_then(|v| v.trim().parse::<u64>().ok()).unwrap_or(30);
+
+    let from = node.config.get("from").map(|v| v.trim()).filter(|v| !v.is_empty()).ok_or_else(|| anyhow!("发件人 from 为空"))?;
+    let subject = node.config.get("subject").cloned().unwrap_or_default();
+    let text = node.config.get("text").filter(|v| !v.is_empty());
+    let html = node.config.get("html").filter(|v| !v.is_empty());
+
+    let mut builder = Message::builder().from(from.parse::<Mailbox>().map_err(|e| anyhow!("发件人地址无效 {}: {}", from, e))?).subject(subject).message_id(None);
+    let mut recipient_count = 0;
+    for (key, address) in ["to", "cc", "bcc"].iter().flat_map(|key| mailboxes(node, key).into_iter().map(move |address| (*key, address))) {
+        let mailbox = address.parse::<Mailbox>().map_err(|e| anyhow!("{} 地址无效 {}: {}", key, address, e))?;
+        builder = match key {
+            "to" => builder.to(mailbox),
+            "cc" => builder.cc(mailbox),
+            _ => builder.bcc(mailbox),
+        };
+        recipient_count += 1;
+    }
+    if recipient_count == 0 {
+        return Err(anyhow!("收件人为空"));
+    }
+
+    let body = match (text, html) {
+        (Some(text), Some(html)) => MultiPart::alternative_plain_html(text.clone(), html.clone()),
+        (None, Some(html)) => MultiPart::alternative().singlepart(SinglePart::html(html.clone())),
+        (text, None) => MultiPart::alternative().singlepart(SinglePart::plain(text.cloned().unwrap_or_default())),
+    };
+
+    let mut content = MultiPart::mixed().multipart(body);
+    for (file_name, mime_type, data) in attachments(node).await? {
+        content = content.singlepart(Attachment::new(file_name).body(data, ContentType::parse(&mime_type)?));
+    }
+
+    let message = builder.multipart(content)?;
+    let message_id = message.headers().get_raw("Message-ID").unwrap_or_default().to_string();
+
+    let tls = match security.as_str() {
+        "none" => Tls::None,
+        "tls" => Tls::Wrapper(TlsParameters::new(host.to_string())?),
+        _ => Tls::Required(TlsParameters::new(host.to_string())?),
+    };
+    let mut transport = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host).port(port).tls(tls).timeout(Some(Duration::from_secs(timeout)));
+    if let Some(username) = username {
+        transport = transport.credentials(Credentials::new(username.to_string(), password));
+    }
+
+    let log_data = LogData { kind: "email-info".to_string(), data: Some(format!("正在通过 {}:{} 发送邮件", host, port)), node_id: node.id.clone(), node_type: None, result: None };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    sse::send_json(log_data, sender)?;
+
+    let response = transport.build().send(message).await.map_err(|e| anyhow!("发送邮件失败: {}", e))?;
+    let output = json!({
+        "messageId": message_id,
+        "code": response.code().to_string(),
+        "response": response.message().collect::<Vec<_>>().join("\n"),
+    })
+    .to_string();
+
+    let log_data = LogData { kind: "output".to_string(), data: Some(output.clone()), node_id: node.id.clone(), node_type: None, result: Some(output.clone()) };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    sse::send_json(log_data, sender)?;
+
+    Ok((logs, output))
+}
+
+/// 附件，返回 (文件名, MIME 类型, 内容)。attachments 每行一个文件路径（例如 write-file 节点写出的文件）
+/// 或 read-file 等节点输出的二进制引用，也可以是二进制引用组成的 JSON 数组（例如 imap-trigger 的 attachments）
+async fn attachments(node: &Node) -> anyhow::Result<Vec<(String, String, Vec<u8>)>> {
+    let config = node.config.get("attachments").map(|v| v.trim()).unwrap_or_default();
+    let entries: Vec<String> = match serde_json::from_str::<Value>(config) {
+        Ok(Value::Array(items)) => items.into_iter().map(|item| if let Value::String(s) = item { s } else { item.to_string() }).collect(),
+        _ => config.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect(),
+    };
+    let mut attachments = vec![];
+    for entry in entries {
+        if let Some(binary) = Binary::parse(&entry) {
+            attachments.push((
+                binary.file_name.clone(),
+                binary.mime_type.clone(),
+                binary.read()?,
+            ));
+            continue;
+        }
+        let data = tokio::fs::read(&entry).await.map_err(|e| anyhow!("读取附件 {} 失败: {}", entry, e))?;
+        let file_name = Path::new(&entry).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "attachment".to_string());
+        attachments.push((
+            file_name,
+            mime_guess::from_path(&entry).first_or_octet_stream().to_string(),
+            data,
+        ));
+    }
+    Ok(attachments)
+}
+
+/// 逗号、分号或换行分隔的地址列表
+fn mailboxes<'a>(node: &'a Node, key: &str) -> Vec<&'a str> {
+    node.config.get(key).map(|v| v.split([',', ';', '\n']).map(|s| s.trim()).filter(|s| !s.is_empty()).collect()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(config: Value) -> Node {
+        serde_json::from_value(json!({ "id": "email", "type": "email", "position": { "x": 0, "y": 0 }, "config": config })).unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "需要 127.0.0.1:1025 上的 SMTP 服务器，例如 mailpit"]
+    async fn send_to_local_server() {
+        let node = node(json!({
+            "host": "127.0.0.1",
+            "port": "1025",
+            "security": "none",
+            "from": "n2s <n2s@example.com>",
+            "to": "a@example.com; b@example.com",
+            "subject": "测试",
+            "text": "hello",
+            "html": "<p>hello</p>",
+        }));
+        let (logs, output) = execute(&node, &None).await.unwrap();
+        let output: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["code"], "250");
+        assert!(output["messageId"].as_str().is_some_and(|id| !id.is_empty()));
+        assert_eq!(logs.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn requires_recipients() {
+        let node = node(json!({ "host": "127.0.0.1", "from": "n2s@example.com" }));
+        assert_eq!(
+            execute(&node, &None).await.unwrap_err().to_string(),
+            "收件人为空"
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_addresses() {
+        let node = node(json!({ "host": "127.0.0.1", "from": "n2s@example.com", "to": "a@example.com, not an address" }));
+        assert!(execute(&node, &None).await.unwrap_err().to_string().starts_with("to 地址无效 not an address"));
+    }
+
+    #[tokio::test]
+    async fn reads_attachments_from_binaries_and_paths() {
+        let report = Binary::save(b"a,b", Some("report.csv"), None).unwrap();
+        let image = Binary::save(b"png", Some("logo.png"), None).unwrap();
+        let path = std::env::temp_dir().join(format!("n2s-{}.txt", uuid::Uuid::new_v4().simple()));
+        std::fs::write(&path, "note").unwrap();
+
+        let config = format!("{}\n  {}\n", report.to_output(), path.display());
+        let found = attachments(&node(json!({ "attachments": config }))).await.unwrap();
+        let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+        assert_eq!(found, [
+            (
+                "report.csv".to_string(),
+                "text/csv".to_string(),
+                b"a,b".to_vec()
+            ),
+            (file_name, "text/plain".to_string(), b"note".to_vec())
+        ]);
+        let _ = std::fs::remove_file(&path);
+
+        let config = json!([report, image]).to_string();
+        let found = attachments(&node(json!({ "attachments": config }))).await.unwrap();
+        assert_eq!(
+            found.iter().map(|(name, mime_type, _)| format!("{} {}", name, mime_type)).collect::<Vec<_>>(),
+            ["report.csv text/csv", "logo.png image/png"]
+        );
+
+        let error = attachments(&node(json!({ "attachments": "/missing/file.pdf" }))).await.unwrap_err();
+        assert!(
+            error.to_string().starts_with("读取附件 /missing/file.pdf 失败"),
+            "{}",
+            error
+        );
+    }
+}
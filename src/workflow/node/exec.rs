@@ -0,0 +1,124 @@
+use std::{convert::Infallible, process::Stdio, time::Duration};
+
+use anyhow::anyhow;
+use axum::response::sse::Event;
+use chrono::Utc;
+use serde_json::json;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader}, process::Command, sync::mpsc::UnboundedSender
+};
+
+use super::super::{
+    model::{Log, LogData, Node}, sse
+};
+use crate::config::CONFIG;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_MAX_OUTPUT: usize = 1024 * 1024;
+
+/// 运行本地程序，返回 (日志, 输出, 连接点)。
+/// 输出为包含 exitCode/stdout/stderr 的 JSON，退出码为 0 时连接点为 success，否则为 failure，两种情况都会沿 always 连接点继续。
+pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String, String)> {
+    if !CONFIG.exec.enabled {
+        return Err(anyhow!(
+            "exec 节点未启用，请在 config.json 中设置 exec.enabled"
+        ));
+    }
+
+    let program = node.config.get("program").map(|v| v.trim().to_string()).unwrap_or_default();
+    if program.is_empty() {
+        return Err(anyhow!("program 为空"));
+    }
+    if !CONFIG.exec.allowed_programs.is_empty() && !CONFIG.exec.allowed_programs.contains(&program) {
+        return Err(anyhow!("程序 {} 不在 exec.allowedPrograms 中", program));
+    }
+
+    // 每行一个参数 / 一个 KEY=VALUE 环境变量
+    let args: Vec<&str> = node.config.get("args").map(|v| v.lines().filter(|l| !l.is_empty()).collect()).unwrap_or_default();
+    let envs: Vec<(&str, &str)> = node.config.get("env").map(|v| v.lines().filter_map(|l| l.split_once('=')).map(|(k, v)| (k.trim(), v)).collect()).unwrap_or_default();
+    let cwd = node.config.get("cwd").map(|v| v.trim()).filter(|v| !v.is_empty());
+    let stdin = node.config.get("stdin").cloned().unwrap_or_default();
+    let timeout = node.config.get("timeout").and_then(|v| v.trim().parse::<u64>().ok()).unwrap_or(DEFAULT_TIMEOUT_SECS);
+    let max_output = node.config.get("maxOutput").and_then(|v| v.trim().parse::<usize>().ok()).unwrap_or(DEFAULT_MAX_OUTPUT);
+
+    let mut command = Command::new(&program);
+    command.args(&args).envs(envs).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true);
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+    let mut child = command.spawn().map_err(|e| anyhow!("启动程序 {} 失败: {}", program, e))?;
+
+    // 写入 stdin 后关闭，避免子进程一直等待输入
+    let mut child_stdin = child.stdin.take().ok_or_else(|| anyhow!("无法获取 stdin"))?;
+    let stdin_task = tokio::spawn(async move {
+        let _ = child_stdin.write_all(stdin.as_bytes()).await;
+    });
+
+    let mut stdout = BufReader::new(child.stdout.take().ok_or_else(|| anyhow!("无法获取 stdout"))?);
+    let mut stderr = BufReader::new(child.stderr.take().ok_or_else(|| anyhow!("无法获取 stderr"))?);
+
+    let mut logs = vec![];
+    let (mut stdout_text, mut stderr_text) = (String::new(), String::new());
+    let mut truncated = false;
+
+    let run = async {
+        let (mut stdout_line, mut stderr_line) = (Vec::new(), Vec::new());
+        let (mut stdout_done, mut stderr_done) = (false, false);
+        while !(stdout_done && stderr_done) {
+            // read_until 被取消时已读取的数据保留在缓冲区中，可安全用于 select!
+            let (kind, line) = tokio::select! {
+                read = stdout.read_until(b'\n', &mut stdout_line), if !stdout_done => {
+                    if read? == 0 { stdout_done = true; }
+                    ("exec_stdout", std::mem::take(&mut stdout_line))
+                }
+                read = stderr.read_until(b'\n', &mut stderr_line), if !stderr_done => {
+                    if read? == 0 { stderr_done = true; }
+                    ("exec_stderr", std::mem::take(&mut stderr_line))
+                }
+            };
+            if line.is_empty() {
+                continue;
+            }
+            let line = String::from_utf8_lossy(&line).to_string();
+            // 超过输出上限后继续读取（避免子进程阻塞），但不再记录
+            if stdout_text.len() + stderr_text.len() + line.len() > max_output {
+                truncated = true;
+                continue;
+            }
+            if kind == "exec_stdout" {
+                stdout_text.push_str(&line)
+            } else {
+                stderr_text.push_str(&line)
+            }
+            let log_data = LogData { kind: kind.to_string(), data: Some(line.trim_end_matches(['\r', '\n']).to_string()), node_id: node.id.clone(), node_type: Some(node.kind.clone()), result: None };
+            logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+            sse::send_json(log_data, sender)?;
+        }
+        anyhow::Ok(child.wait().await?)
+    };
+
+    let (exit_code, timed_out) = match tokio::time::timeout(Duration::from_secs(timeout), run).await {
+        Ok(status) => (status?.code(), false),
+        Err(_) => {
+            let _ = child.kill().await;
+            (None, true)
+        }
+    };
+    stdin_task.abort();
+
+    let output = json!({
+        "exitCode": exit_code,
+        "stdout": stdout_text,
+        "stderr": stderr_text,
+        "truncated": truncated,
+        "timedOut": timed_out,
+    })
+    .to_string();
+    let handle = if exit_code == Some(0) { "success" } else { "failure" };
+
+    let log_data = LogData { kind: "output".to_string(), data: Some(output.clone()), node_id: node.id.clone(), node_type: None, result: Some(handle.to_string()) };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    sse::send_json(log_data, sender)?;
+
+    Ok((logs, output, handle.to_string()))
+}
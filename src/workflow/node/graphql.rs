@@ -0,0 +1,63 @@
+use std::convert::Infallible;
+
+use anyhow::{anyhow, bail};
+use axum::response::sse::Event;
+use chrono::Utc;
+use reqwest::Method;
+use serde_json::{Value, json};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::{
+    super::{
+        model::{Log, LogData, Node}, sse::send_json
+    }, http::{self, Settings}
+};
+
+/// 向 url 发送 GraphQL 查询：query 为查询文档，variables 为 JSON 对象（按 JSON 渲染占位符），operationName 可选。
+/// 认证、超时等设置与 http-request 节点相同；响应中的 errors 作为节点错误，输出 data
+pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
+    let url = node.config.get("url").map(|v| v.trim()).filter(|v| !v.is_empty()).ok_or_else(|| anyhow!("url 为空"))?;
+    let query = node.config.get("query").filter(|v| !v.trim().is_empty()).ok_or_else(|| anyhow!("query 为空"))?;
+    let variables = match node.config.get("variables").filter(|v| !v.trim().is_empty()) {
+        Some(variables) => serde_json::from_str::<Value>(variables).map_err(|e| anyhow!("variables 不是合法的 JSON: {}", e))?,
+        None => json!({}),
+    };
+    if !variables.is_object() {
+        bail!("variables 应为 JSON 对象");
+    }
+    let mut payload = json!({ "query": query, "variables": variables });
+    if let Some(operation_name) = node.config.get("operationName").map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        payload["operationName"] = json!(operation_name);
+    }
+
+    let settings = Settings::new(node)?;
+    let request = settings.request(Method::POST, url).await?.headers(http::headers(node)?).json(&payload);
+    let response = http::response_value(request.send().await.map_err(http::request_error)?).await?;
+
+    let body = &response["body"];
+    if let Some(errors) = body.get("errors").and_then(|errors| errors.as_array()).filter(|errors| !errors.is_empty()) {
+        let messages: Vec<String> = errors
+            .iter()
+            .map(|error| {
+                let message = error.get("message").and_then(|v| v.as_str()).map_or_else(|| error.to_string(), |v| v.to_string());
+                match error.get("path").and_then(|v| v.as_array()) {
+                    Some(path) => format!(
+                        "{} (path: {})",
+                        message,
+                        path.iter().map(|p| p.as_str().map_or_else(|| p.to_string(), |p| p.to_string())).collect::<Vec<_>>().join(".")
+                    ),
+                    None => message,
+                }
+            })
+            .collect();
+        bail!("GraphQL 错误: {}", messages.join("; "));
+    }
+    if !response["ok"].as_bool().unwrap_or_default() || body.get("data").is_none() {
+        bail!("GraphQL 请求失败: {} {}", response["status"], body);
+    }
+
+    let output = body["data"].to_string();
+    let log_data = LogData { kind: "output".to_string(), data: Some(output.clone()), node_id: node.id.clone(), node_type: None, result: Some(output.clone()) };
+    send_json(log_data.clone(), sender)?;
+    Ok((vec![Log { timestamp: Utc::now(), data: log_data }], output))
+}
@@ -1,56 +1,832 @@
-use std::{convert::Infallible, str::FromStr, time::Duration};
+use std::{
+    collections::HashMap, convert::Infallible, str::FromStr, sync::{Mutex, OnceLock}, time::{Duration, Instant}
+};
 
+use anyhow::{anyhow, bail};
 use axum::response::sse::Event;
 use chrono::Utc;
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{
+    Client, Method, RequestBuilder, Response, header::{CONTENT_DISPOSITION, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue}, multipart::{Form, Part}, redirect::Policy
+};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
 use tokio::sync::mpsc::UnboundedSender;
 
 use super::super::{
-    model::{Log, LogData, Node}, sse::send_json
+    binary::Binary, model::{Log, LogData, Node}, sse::send_json
 };
 
+/// 按重定向次数与是否校验证书复用客户端（连接池）
+static CLIENTS: OnceLock<Mutex<HashMap<(usize, bool), Client>>> = OnceLock::new();
+/// OAuth2 客户端凭证模式获取的令牌及其过期时间，按令牌地址、client id 与 scope 缓存
+static TOKENS: OnceLock<Mutex<HashMap<String, (String, Instant)>>> = OnceLock::new();
+
+/// 令牌在过期前提前刷新的时间
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// 认证方式，authType 为 none（默认）、basic、bearer、apiKey 或 oauth2
+enum Auth {
+    None,
+    Basic {
+        username: String,
+        password: String,
+    },
+    Bearer(String),
+    /// apiKeyIn 为 header（默认）或 query
+    ApiKey {
+        name: String,
+        value: String,
+        in_query: bool,
+    },
+    /// 客户端凭证模式，clientAuth 为 basic（默认，凭证放在 Authorization 头）或 body
+    OAuth2 {
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+        scope: Option<String>,
+        in_body: bool,
+    },
+}
+
+/// http-request、graphql 等节点共用的请求设置：timeout（秒，默认 30）、followRedirects（默认 true）、
+/// maxRedirects（默认 10）、verifyTls（默认 true）以及认证配置
+pub struct Settings {
+    timeout: Duration,
+    redirects: usize,
+    verify_tls: bool,
+    auth: Auth,
+}
+
+impl Settings {
+    pub fn new(node: &Node) -> anyhow::Result<Self> {
+        let config = |key: &str| node.config.get(key).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let required = |key: &str| config(key).ok_or_else(|| anyhow!("{} 为空", key));
+        let timeout = match config("timeout") {
+            Some(v) => v.parse::<f64>().ok().filter(|v| *v > 0.0).map(Duration::from_secs_f64).ok_or_else(|| anyhow!("timeout 不是合法的秒数: {}", v))?,
+            None => Duration::from_secs(30),
+        };
+        let redirects = if config("followRedirects").is_some_and(|v| v == "false") {
+            0
+        } else {
+            match config("maxRedirects") {
+                Some(v) => v.parse::<usize>().map_err(|_| anyhow!("maxRedirects 不是合法的整数: {}", v))?,
+                None => 10,
+            }
+        };
+        let auth = match config("authType").map(|v| v.to_lowercase()).as_deref() {
+            None | Some("none") => Auth::None,
+            Some("basic") => Auth::Basic { username: required("username")?, password: node.config.get("password").cloned().unwrap_or_default() },
+            Some("bearer") => Auth::Bearer(required("token")?),
+            Some("apikey") => Auth::ApiKey { name: required("apiKeyName")?, value: required("apiKeyValue")?, in_query: config("apiKeyIn").is_some_and(|v| v == "query") },
+            Some("oauth2") => Auth::OAuth2 {
+                token_url: required("tokenUrl")?,
+                client_id: required("clientId")?,
+                client_secret: node.config.get("clientSecret").cloned().unwrap_or_default(),
+                scope: config("scope"),
+                in_body: config("clientAuth").is_some_and(|v| v == "body"),
+            },
+            Some(other) => bail!("不支持的 authType: {}", other),
+        };
+        Ok(Settings { timeout, redirects, verify_tls: config("verifyTls").is_none_or(|v| v != "false"), auth })
+    }
+
+    pub fn client(&self) -> anyhow::Result<Client> {
+        let mut clients = CLIENTS.get_or_init(Default::default).lock().unwrap();
+        if let Some(client) = clients.get(&(self.redirects, self.verify_tls)) {
+            return Ok(client.clone());
+        }
+        let policy = if self.redirects == 0 { Policy::none() } else { Policy::limited(self.redirects) };
+        let client = Client::builder().redirect(policy).danger_accept_invalid_certs(!self.verify_tls).build()?;
+        clients.insert((self.redirects, self.verify_tls), client.clone());
+        Ok(client)
+    }
+
+    /// 创建请求并设置超时与认证
+    pub async fn request(&self, method: Method, url: &str) -> anyhow::Result<RequestBuilder> {
+        let client = self.client()?;
+        let request = client.request(method, url).timeout(self.timeout);
+        Ok(match &self.auth {
+            Auth::None => request,
+            Auth::Basic { username, password } => request.basic_auth(username, Some(password)),
+            Auth::Bearer(token) => request.bearer_auth(token),
+            Auth::ApiKey { name, value, in_query: true } => request.query(&[(name, value)]),
+            Auth::ApiKey { name, value, in_query: false } => request.header(HeaderName::from_str(name)?, HeaderValue::from_str(value)?),
+            Auth::OAuth2 { .. } => request.bearer_auth(self.access_token(&client).await?),
+        })
+    }
+
+    async fn access_token(&self, client: &Client) -> anyhow::Result<String> {
+        let Auth::OAuth2 { token_url, client_id, client_secret, scope, in_body } = &self.auth else {
+            bail!("未配置 OAuth2 认证");
+        };
+        let key = format!(
+            "{}\n{}\n{}",
+            token_url,
+            client_id,
+            scope.as_deref().unwrap_or_default()
+        );
+        if let Some((token, expires_at)) = TOKENS.get_or_init(Default::default).lock().unwrap().get(&key)
+            && Instant::now() + TOKEN_REFRESH_MARGIN < *expires_at
+        {
+            return Ok(token.clone());
+        }
+
+        let mut form = vec![("grant_type", "client_credentials")];
+        if let Some(scope) = scope {
+            form.push(("scope", scope));
+        }
+        let request = client.post(token_url).timeout(self.timeout);
+        let request = if *in_body {
+            form.push(("client_id", client_id));
+            form.push(("client_secret", client_secret));
+            request
+        } else {
+            request.basic_auth(client_id, Some(client_secret))
+        };
+        let response = request.form(&form).send().await.map_err(|e| anyhow!("获取 OAuth2 令牌失败: {}", e))?;
+        let status = response.status();
+        let body: Value = response.json().await.map_err(|e| anyhow!("OAuth2 令牌响应不是合法的 JSON: {}", e))?;
+        let token = body.get("access_token").and_then(|v| v.as_str()).filter(|_| status.is_success()).ok_or_else(|| anyhow!("获取 OAuth2 令牌失败: {} {}", status, body))?.to_string();
+        let expires_in = body.get("expires_in").and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|v| v.parse().ok()))).unwrap_or(3600);
+        TOKENS.get_or_init(Default::default).lock().unwrap().insert(
+            key,
+            (
+                token.clone(),
+                Instant::now() + Duration::from_secs(expires_in),
+            ),
+        );
+        Ok(token)
+    }
+}
+
 pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
     let mut logs = vec![];
-    let mut output = String::new();
-    let url = node.config.get("url");
-    if url.is_none() {
-        let log_data = LogData { kind: "http-request-error".to_string(), data: Some("url 为空".to_string()), node_id: node.id.clone(), node_type: None, result: None };
-        logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
-        send_json(log_data, sender).unwrap();
-    } else {
-        let url = url.unwrap();
-        let method = node.config.get("method").map(|s| s.as_str()).unwrap_or("GET");
-        let headers_str = node.config.get("headers").map(|s| s.as_str()).unwrap_or("");
-        let body = node.config.get("body").cloned().unwrap_or_default();
-        let client = reqwest::Client::new();
-        let request = client.request(method.parse().unwrap(), url);
-        let mut header_map = HeaderMap::new();
-        if !headers_str.trim().is_empty() {
-            for line in headers_str.trim().lines() {
-                if let Some((k, v)) = line.split_once(':') {
-                    let name = HeaderName::from_str(k.trim()).map_err(|_| "Invalid header name").unwrap();
-                    let value = HeaderValue::from_str(v.trim()).map_err(|_| "Invalid header value").unwrap();
-                    header_map.insert(name, value);
+    let url = node.config.get("url").map(|v| v.trim()).filter(|v| !v.is_empty()).ok_or_else(|| anyhow!("url 为空"))?;
+    let settings = Settings::new(node)?;
+
+    let output = match Pagination::new(node)? {
+        Some(pagination) => Value::Array(paginate(node, &settings, url, &pagination, &mut logs, sender).await?).to_string(),
+        None => {
+            let response = send(node, &settings, url).await?;
+            // 默认输出状态码、响应头与响应体；responseFormat 为 body 时只输出响应体，兼容依赖旧输出的工作流
+            if node.config.get("responseFormat").is_some_and(|v| v.trim() == "body") {
+                match &response["body"] {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
                 }
+            } else {
+                response.to_string()
             }
         }
-        let request = request.headers(header_map);
-        let request = request.body(body.clone()).timeout(Duration::from_secs(3));
-        let response = request.send().await;
-        match response {
-            Ok(response) => {
-                let text = response.text().await.unwrap_or_default();
-                output.push_str(&text);
-                let log_data = LogData { kind: "output".to_string(), data: Some(text.clone()), node_id: node.id.clone(), node_type: None, result: Some(text.clone()) };
-                logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
-                send_json(log_data, sender).unwrap();
+    };
+
+    let log_data = LogData { kind: "output".to_string(), data: Some(output.clone()), node_id: node.id.clone(), node_type: None, result: Some(output.clone()) };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    send_json(log_data, sender)?;
+    Ok((logs, output))
+}
+
+/// 按节点配置的方法、请求头与请求体发送请求，返回结构化的响应
+async fn send(node: &Node, settings: &Settings, url: &str) -> anyhow::Result<Value> {
+    let method = node.config.get("method").map(|v| v.trim().to_uppercase()).filter(|v| !v.is_empty()).unwrap_or("GET".to_string());
+    let method = Method::from_str(&method).map_err(|_| anyhow!("无效的请求方法: {}", method))?;
+    let mut header_map = headers(node)?;
+    let body = node.config.get("body").cloned().unwrap_or_default();
+
+    let request = settings.request(method, url).await?;
+    let request = match node.config.get("bodyType").map(|v| v.trim().to_lowercase()).unwrap_or_default().as_str() {
+        "form" => request.form(&form_fields(node)?),
+        "multipart" => {
+            // 由 reqwest 生成带 boundary 的 Content-Type
+            header_map.remove(CONTENT_TYPE);
+            request.multipart(multipart(node).await?)
+        }
+        "" | "raw" => match Binary::parse(&body) {
+            // 请求体为二进制引用时发送原始字节
+            Some(binary) => {
+                if !header_map.contains_key(CONTENT_TYPE) {
+                    header_map.insert(CONTENT_TYPE, HeaderValue::from_str(&binary.mime_type)?);
+                }
+                request.body(binary.read()?)
+            }
+            None if body.is_empty() => request,
+            None => request.body(body),
+        },
+        other => bail!("不支持的 bodyType: {}", other),
+    };
+    let response = request.headers(header_map).send().await.map_err(request_error)?;
+    response_value(response).await
+}
+
+/// 自动分页，pagination 为 page、offset、cursor 或 link，未配置时只请求一次。
+/// itemsPath 为响应体中数据数组的路径（如 data.items，为空时响应体本身是数组），maxPages 为最多请求的页数（默认 100）
+struct Pagination {
+    mode: PaginationMode,
+    items_path: String,
+    max_pages: usize,
+}
+
+enum PaginationMode {
+    /// pageParam（默认 page）从 startPage（默认 1）开始递增；设置 pageSize 时同时以 pageSizeParam（默认 pageSize）传入，返回条数不足一页即结束
+    Page { param: String, start: u64, size: Option<(String, u64)> },
+    /// offsetParam（默认 offset）从 0 开始按返回条数递增，limitParam（默认 limit）为每页条数 limit（默认 100）
+    Offset { param: String, limit_param: String, limit: u64 },
+    /// 下一页的游标取自响应体中的 cursorPath，以 cursorParam（默认 cursor）传入，游标为空时结束
+    Cursor { path: String, param: String },
+    /// 按响应头 Link 中 rel="next" 的地址请求下一页
+    Link,
+}
+
+impl Pagination {
+    fn new(node: &Node) -> anyhow::Result<Option<Self>> {
+        let config = |key: &str| node.config.get(key).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let number = |key: &str, default: u64| -> anyhow::Result<u64> {
+            config(key).map_or(Ok(default), |v| {
+                v.parse::<u64>().map_err(|_| anyhow!("{} 不是合法的整数: {}", key, v))
+            })
+        };
+        let mode = match config("pagination").map(|v| v.to_lowercase()).as_deref() {
+            None | Some("none") => return Ok(None),
+            Some("page") => PaginationMode::Page {
+                param: config("pageParam").unwrap_or("page".to_string()),
+                start: number("startPage", 1)?,
+                size: config("pageSize")
+                    .map(|_| {
+                        Ok::<_, anyhow::Error>((
+                            config("pageSizeParam").unwrap_or("pageSize".to_string()),
+                            number("pageSize", 0)?,
+                        ))
+                    })
+                    .transpose()?,
+            },
+            Some("offset") => {
+                PaginationMode::Offset { param: config("offsetParam").unwrap_or("offset".to_string()), limit_param: config("limitParam").unwrap_or("limit".to_string()), limit: number("limit", 100)? }
             }
-            Err(e) => {
-                let log_data = LogData { kind: "output".to_string(), data: Some(format!("error: {}", e)), node_id: node.id.clone(), node_type: None, result: None };
-                logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
-                send_json(log_data, sender).unwrap();
+            Some("cursor") => PaginationMode::Cursor { path: config("cursorPath").ok_or_else(|| anyhow!("cursorPath 为空"))?, param: config("cursorParam").unwrap_or("cursor".to_string()) },
+            Some("link") => PaginationMode::Link,
+            Some(other) => bail!("不支持的 pagination: {}", other),
+        };
+        Ok(Some(Pagination {
+            mode,
+            items_path: config("itemsPath").unwrap_or_default(),
+            max_pages: number("maxPages", 100)? as usize,
+        }))
+    }
+}
+
+/// 逐页请求直到没有更多数据或达到 maxPages，返回合并后的数据
+async fn paginate(
+    node: &Node, settings: &Settings, url: &str, pagination: &Pagination, logs: &mut Vec<Log>, sender: &Option<UnboundedSender<Result<Event, Infallible>>>,
+) -> anyhow::Result<Vec<Value>> {
+    let mut items = Vec::new();
+    let mut next_url = Some(match &pagination.mode {
+        PaginationMode::Page { param, start, size } => {
+            let mut params = vec![(param.clone(), start.to_string())];
+            params.extend(size.iter().map(|(size_param, size)| (size_param.clone(), size.to_string())));
+            with_query(url, &params)?
+        }
+        PaginationMode::Offset { param, limit_param, limit } => with_query(url, &[
+            (param.clone(), "0".to_string()),
+            (limit_param.clone(), limit.to_string()),
+        ])?,
+        PaginationMode::Cursor { .. } | PaginationMode::Link => url.to_string(),
+    });
+    let mut page = 0;
+    while let Some(current) = next_url.take() {
+        if page >= pagination.max_pages {
+            break;
+        }
+        page += 1;
+        let response = send(node, settings, &current).await?;
+        if !response["ok"].as_bool().unwrap_or_default() {
+            bail!(
+                "第 {} 页请求失败: {} {}",
+                page,
+                response["status"],
+                response["body"]
+            );
+        }
+        let page_items = match json_path(&response["body"], &pagination.items_path) {
+            Some(Value::Array(page_items)) => page_items.clone(),
+            Some(Value::Null) | None => vec![],
+            Some(_) => bail!("第 {} 页的 {} 不是数组", page, pagination.items_path),
+        };
+        let count = page_items.len() as u64;
+        items.extend(page_items);
+
+        let log_data = LogData { kind: "http-request-info".to_string(), data: Some(format!("第 {} 页返回 {} 条", page, count)), node_id: node.id.clone(), node_type: None, result: None };
+        logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+        send_json(log_data, sender)?;
+
+        next_url = match &pagination.mode {
+            _ if count == 0 => None,
+            PaginationMode::Page { size: Some((_, size)), .. } if count < *size => None,
+            PaginationMode::Page { param, start, .. } => Some(with_query(&current, &[(
+                param.clone(),
+                (start + page as u64).to_string(),
+            )])?),
+            PaginationMode::Offset { limit, .. } if count < *limit => None,
+            PaginationMode::Offset { param, .. } => Some(with_query(&current, &[(
+                param.clone(),
+                items.len().to_string(),
+            )])?),
+            PaginationMode::Cursor { path, param } => match json_path(&response["body"], path) {
+                Some(Value::Null) | None => None,
+                Some(Value::String(cursor)) if cursor.is_empty() => None,
+                Some(Value::String(cursor)) => Some(with_query(&current, &[(param.clone(), cursor.clone())])?),
+                Some(cursor) => Some(with_query(&current, &[(
+                    param.clone(),
+                    cursor.to_string(),
+                )])?),
+            },
+            PaginationMode::Link => {
+                response["headers"]["link"].as_str().and_then(next_link).map(|next| reqwest::Url::parse(&current).and_then(|base| base.join(&next))).transpose()?.map(|next| next.to_string())
             }
+        };
+        // 游标或链接不再变化时结束，避免重复请求同一页
+        if next_url.as_deref() == Some(current.as_str()) {
+            break;
         }
     }
-    Ok((logs, output))
+    Ok(items)
+}
+
+/// 设置查询参数，替换同名的已有参数
+fn with_query(url: &str, params: &[(String, String)]) -> anyhow::Result<String> {
+    let mut url = reqwest::Url::parse(url).map_err(|e| anyhow!("无效的 url {}: {}", url, e))?;
+    let existing: Vec<(String, String)> = url.query_pairs().filter(|(name, _)| params.iter().all(|(param, _)| param != name)).map(|(name, value)| (name.to_string(), value.to_string())).collect();
+    url.query_pairs_mut().clear().extend_pairs(existing).extend_pairs(params);
+    Ok(url.to_string())
+}
+
+/// Link: <https://api.example.com/items?page=2>; rel="next", <...>; rel="last"
+fn next_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|link| {
+        let (target, params) = link.split_once(';')?;
+        let is_next = params.split(';').any(|param| param.trim().strip_prefix("rel=").is_some_and(|rel| rel.trim_matches('"').split_whitespace().any(|rel| rel == "next")));
+        is_next.then(|| target.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+/// 按 a.b[0].c 形式的路径取值，可以 $ 开头，路径为空时返回值本身
+fn json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim().trim_start_matches('$').trim_start_matches('.');
+    let mut current = value;
+    for part in path.split('.').filter(|part| !part.is_empty()) {
+        let (key, indexes) = part.split_once('[').map_or((part, ""), |(key, rest)| (key, rest));
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        for index in indexes.split('[').filter(|index| !index.is_empty()) {
+            current = current.get(index.trim_end_matches(']').parse::<usize>().ok()?)?;
+        }
+    }
+    Some(current)
+}
+
+/// form、multipart 请求体的字段。fields 配置为 JSON 对象 {"名称": "值"}，或字段数组：
+/// [{"name": "title", "value": "${input}"}, {"name": "upload", "file": "${input}", "fileName": "a.pdf", "mimeType": "application/pdf"}]。
+/// file 必须是 read-file 等节点输出的二进制引用，不能直接读取服务器上的文件；value 为二进制引用时同样作为文件上传
+#[derive(Deserialize)]
+struct Field {
+    name: String,
+    #[serde(default)]
+    value: Value,
+    file: Option<String>,
+    #[serde(rename = "fileName")]
+    file_name: Option<String>,
+    #[serde(rename = "mimeType")]
+    mime_type: Option<String>,
+}
+
+impl Field {
+    fn text(&self) -> String {
+        match &self.value {
+            Value::Null => String::new(),
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
+fn fields(node: &Node) -> anyhow::Result<Vec<Field>> {
+    let Some(config) = node.config.get("fields").filter(|v| !v.trim().is_empty()) else {
+        return Ok(vec![]);
+    };
+    let fields = match serde_json::from_str(config).map_err(|e| anyhow!("fields 配置不是合法的 JSON: {}", e))? {
+        Value::Object(map) => map.into_iter().map(|(name, value)| Field { name, value, file: None, file_name: None, mime_type: None }).collect(),
+        fields @ Value::Array(_) => serde_json::from_value(fields).map_err(|e| anyhow!("fields 配置格式错误: {}", e))?,
+        _ => bail!("fields 配置应为 JSON 对象或数组"),
+    };
+    Ok(fields)
+}
+
+fn form_fields(node: &Node) -> anyhow::Result<Vec<(String, String)>> {
+    fields(node)?
+        .into_iter()
+        .map(|field| {
+            if field.file.is_some() {
+                bail!("form 请求体不支持文件字段 {}，请使用 multipart", field.name);
+            }
+            Ok((field.name.clone(), field.text()))
+        })
+        .collect()
+}
+
+async fn multipart(node: &Node) -> anyhow::Result<Form> {
+    let mut form = Form::new();
+    for field in fields(node)? {
+        let text = field.text();
+        let binary = match &field.file {
+            Some(file) => Some(Binary::parse(file).ok_or_else(|| anyhow!("字段 {} 的 file 应为二进制数据引用", field.name))?),
+            None => Binary::parse(&text),
+        };
+        let Some(binary) = binary else {
+            form = form.text(field.name, text);
+            continue;
+        };
+        let (content, file_name, mime_type) = (binary.read()?, binary.file_name, binary.mime_type);
+        let part = Part::bytes(content).file_name(field.file_name.unwrap_or(file_name));
+        let mime_type = field.mime_type.unwrap_or(mime_type);
+        let part = part.mime_str(&mime_type).map_err(|_| anyhow!("字段 {} 的 mimeType 无效: {}", field.name, mime_type))?;
+        form = form.part(field.name, part);
+    }
+    Ok(form)
+}
+
+pub fn request_error(e: reqwest::Error) -> anyhow::Error {
+    if e.is_timeout() {
+        anyhow!(
+            "请求超时: {}",
+            e.url().map(|url| url.as_str()).unwrap_or_default()
+        )
+    } else {
+        anyhow!("请求失败: {:#}", anyhow!(e))
+    }
+}
+
+/// headers 配置每行一个 "名称: 值"
+pub fn headers(node: &Node) -> anyhow::Result<HeaderMap> {
+    let mut header_map = HeaderMap::new();
+    for line in node.config.get("headers").map_or("", |v| v.as_str()).lines().filter(|line| !line.trim().is_empty()) {
+        let (name, value) = line.split_once(':').ok_or_else(|| anyhow!("请求头格式应为 名称: 值，实际为 {}", line.trim()))?;
+        let name = HeaderName::from_str(name.trim()).map_err(|_| anyhow!("无效的请求头名称: {}", name.trim()))?;
+        let value = HeaderValue::from_str(value.trim()).map_err(|_| anyhow!("请求头 {} 的值无效", name))?;
+        header_map.append(name, value);
+    }
+    Ok(header_map)
+}
+
+/// 结构化的响应：status、statusText、ok、url、headers 与 body；JSON 响应体解析为 JSON，二进制内容为二进制引用
+pub async fn response_value(response: Response) -> anyhow::Result<Value> {
+    let status = response.status();
+    let url = response.url().to_string();
+    let mut header_map = Map::new();
+    for (name, value) in response.headers() {
+        let value = String::from_utf8_lossy(value.as_bytes()).to_string();
+        match header_map.get_mut(name.as_str()) {
+            // 同名响应头以逗号合并
+            Some(Value::String(existing)) => {
+                existing.push_str(", ");
+                existing.push_str(&value);
+            }
+            _ => {
+                header_map.insert(name.as_str().to_string(), Value::String(value));
+            }
+        }
+    }
+    let is_json = header_map.get(CONTENT_TYPE.as_str()).and_then(|v| v.as_str()).is_some_and(|v| v.contains("json"));
+    let text = response_output(response).await.map_err(|e| anyhow!("读取响应失败: {}", e))?;
+    let body = match Binary::parse(&text) {
+        Some(binary) => serde_json::to_value(binary)?,
+        None if is_json => serde_json::from_str(&text).unwrap_or(Value::String(text)),
+        None => Value::String(text),
+    };
+    Ok(json!({
+        "status": status.as_u16(),
+        "statusText": status.canonical_reason().unwrap_or_default(),
+        "ok": status.is_success(),
+        "url": url,
+        "headers": header_map,
+        "body": body,
+    }))
+}
+
+/// 文本响应输出内容；图片、PDF 等二进制响应保存为二进制数据，输出其引用
+async fn response_output(response: Response) -> anyhow::Result<String> {
+    let mime_type = response.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let file_name = file_name(&response);
+    let bytes = response.bytes().await?;
+    let essence = mime_type.as_deref().and_then(|v| v.split(';').next()).unwrap_or("").trim().to_lowercase();
+    let textual = essence.is_empty() || essence.starts_with("text/") || ["json", "xml", "javascript", "x-www-form-urlencoded", "yaml"].iter().any(|kind| essence.contains(kind));
+    if textual && let Ok(text) = std::str::from_utf8(&bytes) {
+        return Ok(text.to_string());
+    }
+    Ok(Binary::save(&bytes, file_name.as_deref(), Some(essence.as_str()))?.to_output())
+}
+
+/// 文件名取自 Content-Disposition，否则取 URL 路径的最后一段
+fn file_name(response: &Response) -> Option<String> {
+    let disposition = response.headers().get(CONTENT_DISPOSITION).and_then(|v| v.to_str().ok()).and_then(|v| v.split(';').find_map(|part| part.trim().strip_prefix("filename=")));
+    disposition
+        .map(|name| name.trim_matches('"').to_string())
+        .or_else(|| response.url().path_segments().and_then(|mut segments| segments.next_back()).filter(|name| !name.is_empty()).map(|name| name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc, atomic::{AtomicUsize, Ordering}
+    };
+
+    use axum::{
+        Form, Json, Router, extract::{Path, Query, State}, http::{HeaderMap as AxumHeaders, Method as AxumMethod, StatusCode}, routing::{any, post}
+    };
+
+    use super::*;
+
+    /// 本地测试服务：/echo 返回收到的请求，/token 发放 OAuth2 令牌并记录调用次数
+    async fn serve() -> (String, Arc<AtomicUsize>) {
+        let token_calls = Arc::new(AtomicUsize::new(0));
+        let app = Router::new().route("/echo", any(echo)).route("/status/{code}", any(status)).route("/token", post(token)).route("/items", any(items)).with_state(token_calls.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, token_calls)
+    }
+
+    async fn echo(method: AxumMethod, headers: AxumHeaders, Query(query): Query<HashMap<String, String>>, body: String) -> Json<Value> {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        Json(json!({ "method": method.as_str(), "authorization": header("authorization"), "apiKey": header("x-api-key"), "query": query, "body": body }))
+    }
+
+    /// 分页数据 1 到 7：page 与 size、offset 与 limit、cursor（下一页起始位置）或 Link 响应头；fail 为出错的页码
+    async fn items(Query(query): Query<HashMap<String, String>>) -> (StatusCode, AxumHeaders, Json<Value>) {
+        let number = |name: &str| query.get(name).and_then(|v| v.parse::<usize>().ok());
+        let size = number("size").or(number("limit")).unwrap_or(3);
+        let page = number("page").unwrap_or(1);
+        let start = number("offset").or(number("cursor")).unwrap_or((page - 1) * size);
+        let mut headers = AxumHeaders::new();
+        if number("fail") == Some(page) {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                headers,
+                Json(json!({ "error": "boom" })),
+            );
+        }
+        let data: Vec<usize> = (start + 1..=7).take(size).collect();
+        let next = start + data.len();
+        if next < 7 {
+            headers.insert(
+                "link",
+                format!(
+                    "</items?page={}>; rel=\"next\", </items?page=3>; rel=\"last\"",
+                    page + 1
+                )
+                .parse()
+                .unwrap(),
+            );
+        }
+        (
+            StatusCode::OK,
+            headers,
+            Json(json!({ "data": data, "next": (next < 7).then_some(next) })),
+        )
+    }
+
+    async fn status(Path(code): Path<u16>) -> (StatusCode, &'static str) {
+        (StatusCode::from_u16(code).unwrap(), "failed")
+    }
+
+    async fn token(State(calls): State<Arc<AtomicUsize>>, headers: AxumHeaders, Form(form): Form<HashMap<String, String>>) -> (StatusCode, Json<Value>) {
+        let basic = headers.get("authorization").is_some_and(|v| v == "Basic aWQ6c2VjcmV0");
+        let body = form.get("client_id").is_some_and(|v| v == "id") && form.get("client_secret").is_some_and(|v| v == "secret");
+        if form.get("grant_type").is_none_or(|v| v != "client_credentials") || !(basic || body) {
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({ "error": "invalid_client" })),
+            );
+        }
+        let count = calls.fetch_add(1, Ordering::SeqCst) + 1;
+        (
+            StatusCode::OK,
+            Json(json!({ "access_token": format!("token-{}", count), "expires_in": 3600 })),
+        )
+    }
+
+    fn node(config: Value) -> Node {
+        serde_json::from_value(json!({ "id": "http", "type": "http-request", "position": { "x": 0, "y": 0 }, "config": config })).unwrap()
+    }
+
+    async fn request(config: Value) -> anyhow::Result<Value> {
+        let (_, output) = execute(&node(config), &None).await?;
+        Ok(serde_json::from_str(&output)?)
+    }
+
+    #[tokio::test]
+    async fn returns_structured_response() {
+        let (url, _) = serve().await;
+        let response = request(json!({ "url": format!("{}/echo?a=1", url), "method": "post", "body": "hello", "headers": "X-A: 1" })).await.unwrap();
+        assert_eq!(response["status"], 200);
+        assert_eq!(response["ok"], true);
+        assert_eq!(response["headers"]["content-type"], "application/json");
+        assert_eq!(response["body"]["method"], "POST");
+        assert_eq!(response["body"]["query"], json!({ "a": "1" }));
+        assert_eq!(response["body"]["body"], "hello");
+
+        let response = request(json!({ "url": format!("{}/status/503", url) })).await.unwrap();
+        assert_eq!(
+            (
+                response["status"].clone(),
+                response["ok"].clone(),
+                response["body"].clone()
+            ),
+            (json!(503), json!(false), json!("failed"))
+        );
+    }
+
+    #[tokio::test]
+    async fn outputs_only_body_in_compatibility_mode() {
+        let (url, _) = serve().await;
+        let config = json!({ "url": format!("{}/status/404", url), "responseFormat": "body" });
+        assert_eq!(execute(&node(config), &None).await.unwrap().1, "failed");
+    }
+
+    #[tokio::test]
+    async fn sends_form_and_multipart_fields_from_binaries_only() {
+        let (url, _) = serve().await;
+        let echo = format!("{}/echo", url);
+        let config = json!({ "url": echo, "method": "POST", "bodyType": "form", "fields": r#"{"a": "1 2", "b": "x&y"}"# });
+        assert_eq!(
+            request(config).await.unwrap()["body"]["body"],
+            "a=1+2&b=x%26y"
+        );
+
+        let binary = Binary::save(b"col\n1", Some("data.csv"), None).unwrap();
+        let fields = json!([{ "name": "title", "value": "t" }, { "name": "upload", "file": binary.to_output() }, { "name": "raw", "value": binary.to_output() }]);
+        let config = json!({ "url": echo, "method": "POST", "bodyType": "multipart", "fields": fields.to_string() });
+        let body = request(config).await.unwrap()["body"]["body"].as_str().unwrap().to_string();
+        assert!(
+            body.contains("name=\"upload\"; filename=\"data.csv\"\r\nContent-Type: text/csv\r\n\r\ncol\n1"),
+            "{}",
+            body
+        );
+        assert!(
+            body.contains("name=\"raw\"; filename=\"data.csv\""),
+            "{}",
+            body
+        );
+        assert!(body.contains("name=\"title\"\r\n\r\nt"), "{}", body);
+
+        let fields = json!([{ "name": "upload", "file": "/etc/passwd" }]);
+        let config = json!({ "url": echo, "method": "POST", "bodyType": "multipart", "fields": fields.to_string() });
+        assert_eq!(
+            request(config).await.unwrap_err().to_string(),
+            "字段 upload 的 file 应为二进制数据引用"
+        );
+    }
+
+    #[tokio::test]
+    async fn applies_auth_modes() {
+        let (url, _) = serve().await;
+        let echo = format!("{}/echo", url);
+        let body = request(json!({ "url": echo, "authType": "basic", "username": "u", "password": "p" })).await.unwrap()["body"].clone();
+        assert_eq!(body["authorization"], "Basic dTpw");
+        let body = request(json!({ "url": echo, "authType": "bearer", "token": "abc" })).await.unwrap()["body"].clone();
+        assert_eq!(body["authorization"], "Bearer abc");
+        let body = request(json!({ "url": echo, "authType": "apiKey", "apiKeyName": "X-Api-Key", "apiKeyValue": "k" })).await.unwrap()["body"].clone();
+        assert_eq!(body["apiKey"], "k");
+        let body = request(json!({ "url": echo, "authType": "apiKey", "apiKeyName": "key", "apiKeyValue": "k", "apiKeyIn": "query" })).await.unwrap()["body"].clone();
+        assert_eq!(
+            (body["apiKey"].clone(), body["query"].clone()),
+            (Value::Null, json!({ "key": "k" }))
+        );
+    }
+
+    #[tokio::test]
+    async fn caches_oauth2_tokens() {
+        let (url, calls) = serve().await;
+        let config = json!({ "url": format!("{}/echo", url), "authType": "oauth2", "tokenUrl": format!("{}/token", url), "clientId": "id", "clientSecret": "secret", "scope": "read" });
+        for _ in 0..2 {
+            assert_eq!(
+                request(config.clone()).await.unwrap()["body"]["authorization"],
+                "Bearer token-1"
+            );
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // 凭证放在请求体中，scope 不同时单独缓存
+        let mut config = config;
+        config["clientAuth"] = json!("body");
+        config["scope"] = json!("write");
+        assert_eq!(
+            request(config.clone()).await.unwrap()["body"]["authorization"],
+            "Bearer token-2"
+        );
+
+        config["clientSecret"] = json!("wrong");
+        config["scope"] = json!("admin");
+        let error = request(config).await.unwrap_err().to_string();
+        assert!(
+            error.starts_with("获取 OAuth2 令牌失败: 401 Unauthorized"),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_config() {
+        let cases = [
+            (json!({}), "url 为空"),
+            (
+                json!({ "url": "http://127.0.0.1:1", "method": "GE T" }),
+                "无效的请求方法: GE T",
+            ),
+            (
+                json!({ "url": "http://127.0.0.1:1", "authType": "digest" }),
+                "不支持的 authType: digest",
+            ),
+            (
+                json!({ "url": "http://127.0.0.1:1", "authType": "basic" }),
+                "username 为空",
+            ),
+            (
+                json!({ "url": "http://127.0.0.1:1", "authType": "oauth2", "tokenUrl": "http://127.0.0.1:1/token" }),
+                "clientId 为空",
+            ),
+            (
+                json!({ "url": "http://127.0.0.1:1", "timeout": "-1" }),
+                "timeout 不是合法的秒数: -1",
+            ),
+            (
+                json!({ "url": "http://127.0.0.1:1", "maxRedirects": "many" }),
+                "maxRedirects 不是合法的整数: many",
+            ),
+            (
+                json!({ "url": "http://127.0.0.1:1", "headers": "X-A" }),
+                "请求头格式应为 名称: 值，实际为 X-A",
+            ),
+        ];
+        for (config, message) in cases {
+            assert_eq!(request(config).await.unwrap_err().to_string(), message);
+        }
+    }
+
+    #[tokio::test]
+    async fn paginates_every_mode() {
+        let (url, _) = serve().await;
+        let items = format!("{}/items", url);
+        let configs = [
+            json!({ "pagination": "page", "sizeParam": "size", "pageSize": "3" }),
+            json!({ "pagination": "offset", "limit": "3" }),
+            json!({ "pagination": "cursor", "cursorPath": "next" }),
+            json!({ "pagination": "link" }),
+        ];
+        for mut config in configs {
+            config["url"] = json!(items);
+            config["itemsPath"] = json!("$.data");
+            let (logs, output) = execute(&node(config.clone()), &None).await.unwrap();
+            assert_eq!(
+                serde_json::from_str::<Value>(&output).unwrap(),
+                json!([1, 2, 3, 4, 5, 6, 7]),
+                "{}",
+                config
+            );
+            assert_eq!(logs.len(), 4, "{}", config);
+        }
+    }
+
+    #[tokio::test]
+    async fn stops_at_max_pages_and_failed_pages() {
+        let (url, _) = serve().await;
+        let config = json!({ "url": format!("{}/items", url), "pagination": "page", "itemsPath": "data", "maxPages": "2" });
+        let (_, output) = execute(&node(config), &None).await.unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&output).unwrap(),
+            json!([1, 2, 3, 4, 5, 6])
+        );
+
+        let config = json!({ "url": format!("{}/items?fail=2", url), "pagination": "page", "itemsPath": "data" });
+        let error = execute(&node(config), &None).await.unwrap_err().to_string();
+        assert_eq!(error, r#"第 2 页请求失败: 500 {"error":"boom"}"#);
+
+        let config = json!({ "url": format!("{}/items", url), "pagination": "page", "itemsPath": "next" });
+        assert_eq!(
+            execute(&node(config), &None).await.unwrap_err().to_string(),
+            "第 1 页的 next 不是数组"
+        );
+    }
+
+    #[test]
+    fn parses_link_header_and_json_path() {
+        assert_eq!(
+            next_link(r#"<https://a.com/x?page=3>; rel="last", <https://a.com/x?page=2>; rel="next""#).as_deref(),
+            Some("https://a.com/x?page=2")
+        );
+        assert_eq!(next_link(r#"<https://a.com/x?page=1>; rel="prev""#), None);
+        let value = json!({ "a": { "b": [{ "c": 1 }, { "c": [5, 6] }] } });
+        assert_eq!(json_path(&value, "$.a.b[1].c[0]"), Some(&json!(5)));
+        assert_eq!(json_path(&value, "a.x"), None);
+        assert_eq!(json_path(&value, ""), Some(&value));
+    }
 }
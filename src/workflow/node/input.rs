@@ -12,8 +12,8 @@ pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event,
     let input = node.config.get("input").map(|v| v.to_string());
     let log_data = LogData { kind: "input".to_string(), data: input.clone(), node_id: node.id.clone(), node_type: None, result: None };
     sse::send_json(log_data.clone(), sender)?;
-    return Ok((
+    Ok((
         vec![Log { timestamp: Utc::now(), data: log_data }],
         input.unwrap_or("".to_string()),
-    ));
+    ))
 }
@@ -0,0 +1,423 @@
+use std::{
+    collections::HashMap, convert::Infallible, net::{IpAddr, SocketAddr}, sync::{
+        Arc, Mutex, OnceLock, atomic::{AtomicU64, Ordering}
+    }, time::Duration
+};
+
+use anyhow::{anyhow, bail};
+use axum::response::sse::Event;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{SecondsFormat, Utc};
+use log::info;
+use mlua::{Lua, LuaSerdeExt, Variadic};
+use reqwest::{
+    Client, Method, Url, dns::{Addrs, Name, Resolve, Resolving}, redirect::Policy
+};
+use ring::{digest, hmac};
+use serde_json::{Map, Value};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::{
+    super::{
+        model::{Log, LogData}, sse
+    }, http
+};
+use crate::config::CONFIG;
+
+static KV_FILE: &str = "lua_kv.json";
+static KV: OnceLock<tokio::sync::Mutex<HashMap<String, Map<String, Value>>>> = OnceLock::new();
+/// kv 每次修改的版本号与已写入文件的版本号，较早的修改不会覆盖较新的文件内容
+static KV_VERSION: AtomicU64 = AtomicU64::new(0);
+static KV_SAVED: Mutex<u64> = Mutex::new(0);
+static CLIENT: OnceLock<Client> = OnceLock::new();
+const MAX_REDIRECTS: usize = 10;
+
+pub type EventSender = Option<UnboundedSender<Result<Event, Infallible>>>;
+
+/// 宿主函数所属的执行：节点 id、工作流 id、SSE 通道（节点结束时取走，避免仍在后台执行的脚本使连接保持打开），
+/// log.* 写入的日志收集在 logs 中
+pub struct Host {
+    pub node_id: String,
+    pub workflow_id: String,
+    pub sender: Arc<Mutex<EventSender>>,
+    pub logs: Arc<Mutex<Vec<Log>>>,
+}
+
+impl Host {
+    fn log(&self, level: &str, message: String) -> anyhow::Result<()> {
+        info!("Lua 脚本 {} [{}]: {}", self.node_id, level, message);
+        let log_data = LogData { kind: format!("lua-{}", level), data: Some(message), node_id: self.node_id.clone(), node_type: None, result: None };
+        self.logs.lock().unwrap().push(Log { timestamp: Utc::now(), data: log_data.clone() });
+        let sender = self.sender.lock().unwrap().clone();
+        sse::send_json(log_data, &sender)
+    }
+}
+
+/// 注册脚本可用的宿主函数：http.get / http.post、log.info / warn / error、kv.get / kv.set（按工作流隔离并持久化，受键数与大小限制）、
+/// sleep、uuid、now、base64.encode / decode、hash.sha1 / sha256 / sha512 / hmac_sha256。
+/// 涉及等待的函数为异步函数，不阻塞运行时；执行时间计入脚本的超时限制
+pub fn register(lua: &Lua, host: Host) -> mlua::Result<()> {
+    let globals = lua.globals();
+    let host = Arc::new(host);
+
+    let http = lua.create_table()?;
+    http.set(
+        "get",
+        lua.create_async_function(|lua, (url, options): (String, Option<mlua::Table>)| async move { request(&lua, Method::GET, &url, mlua::Value::Nil, options).await })?,
+    )?;
+    http.set(
+        "post",
+        lua.create_async_function(|lua, (url, body, options): (String, mlua::Value, Option<mlua::Table>)| async move { request(&lua, Method::POST, &url, body, options).await })?,
+    )?;
+    globals.set("http", http)?;
+
+    let log = lua.create_table()?;
+    for level in ["info", "warn", "error"] {
+        let host = host.clone();
+        log.set(
+            level,
+            lua.create_function(move |lua, values: Variadic<mlua::Value>| {
+                let message = values.into_iter().map(|value| display(lua, value)).collect::<mlua::Result<Vec<_>>>()?.join(" ");
+                host.log(level, message).map_err(mlua::Error::external)
+            })?,
+        )?;
+    }
+    globals.set("log", log)?;
+
+    let kv = lua.create_table()?;
+    let workflow_id = host.workflow_id.clone();
+    kv.set(
+        "get",
+        lua.create_async_function(move |lua, key: String| {
+            let workflow_id = workflow_id.clone();
+            async move {
+                let store = kv_store().lock().await;
+                match store.get(&workflow_id).and_then(|values| values.get(&key)) {
+                    Some(value) => lua.to_value(value),
+                    None => Ok(mlua::Value::Nil),
+                }
+            }
+        })?,
+    )?;
+    let workflow_id = host.workflow_id.clone();
+    kv.set(
+        "set",
+        lua.create_async_function(move |lua, (key, value): (String, mlua::Value)| {
+            let workflow_id = workflow_id.clone();
+            async move {
+                let value = match value {
+                    mlua::Value::Nil => None,
+                    value => Some(lua.from_value::<Value>(value)?),
+                };
+                let (version, json_string) = {
+                    let mut store = kv_store().lock().await;
+                    if !update_kv(&mut store, &workflow_id, key, value).map_err(mlua::Error::external)? {
+                        return Ok(());
+                    }
+                    (
+                        KV_VERSION.fetch_add(1, Ordering::Relaxed) + 1,
+                        serde_json::to_string_pretty(&*store).map_err(mlua::Error::external)?,
+                    )
+                };
+                // 在阻塞线程中写入文件，不占用运行时也不持有 kv 的锁
+                tokio::task::spawn_blocking(move || {
+                    let mut saved = KV_SAVED.lock().unwrap();
+                    if version > *saved {
+                        std::fs::write(KV_FILE, json_string).map_err(|e| anyhow!("保存 {} 失败: {}", KV_FILE, e))?;
+                        *saved = version;
+                    }
+                    Ok::<_, anyhow::Error>(())
+                })
+                .await
+                .map_err(mlua::Error::external)?
+                .map_err(mlua::Error::external)
+            }
+        })?,
+    )?;
+    globals.set("kv", kv)?;
+
+    globals.set(
+        "sleep",
+        lua.create_async_function(|_, seconds: f64| async move {
+            if !seconds.is_finite() || seconds < 0.0 {
+                return Err(mlua::Error::external(anyhow!(
+                    "sleep 的秒数无效: {}",
+                    seconds
+                )));
+            }
+            tokio::time::sleep(Duration::try_from_secs_f64(seconds).unwrap_or(Duration::MAX)).await;
+            Ok(())
+        })?,
+    )?;
+    globals.set(
+        "uuid",
+        lua.create_function(|_, ()| Ok(uuid::Uuid::new_v4().to_string()))?,
+    )?;
+    globals.set(
+        "now",
+        lua.create_function(|_, ()| Ok(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)))?,
+    )?;
+
+    let base64 = lua.create_table()?;
+    base64.set(
+        "encode",
+        lua.create_function(|_, data: mlua::String| Ok(STANDARD.encode(data.as_bytes())))?,
+    )?;
+    base64.set(
+        "decode",
+        lua.create_function(|lua, text: mlua::String| {
+            let bytes = STANDARD.decode(text.as_bytes().trim_ascii()).map_err(|e| mlua::Error::external(anyhow!("base64 解码失败: {}", e)))?;
+            lua.create_string(bytes)
+        })?,
+    )?;
+    globals.set("base64", base64)?;
+
+    let hash = lua.create_table()?;
+    for (name, algorithm) in [("sha1", &digest::SHA1_FOR_LEGACY_USE_ONLY), ("sha256", &digest::SHA256), ("sha512", &digest::SHA512)] {
+        hash.set(
+            name,
+            lua.create_function(move |_, data: mlua::String| Ok(hex(digest::digest(algorithm, &data.as_bytes()).as_ref())))?,
+        )?;
+    }
+    hash.set(
+        "hmac_sha256",
+        lua.create_function(|_, (key, data): (mlua::String, mlua::String)| {
+            let key = hmac::Key::new(hmac::HMAC_SHA256, &key.as_bytes());
+            Ok(hex(hmac::sign(&key, &data.as_bytes()).as_ref()))
+        })?,
+    )?;
+    globals.set("hash", hash)?;
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// log.* 的参数以空格连接：字符串原样输出，表转换为 JSON
+fn display(lua: &Lua, value: mlua::Value) -> mlua::Result<String> {
+    match value {
+        mlua::Value::String(s) => Ok(s.to_string_lossy()),
+        mlua::Value::Table(_) => match lua.from_value::<Value>(value.clone()) {
+            Ok(json) => Ok(json.to_string()),
+            Err(_) => value.to_string(),
+        },
+        value => value.to_string(),
+    }
+}
+
+fn kv_store() -> &'static tokio::sync::Mutex<HashMap<String, Map<String, Value>>> {
+    KV.get_or_init(|| tokio::sync::Mutex::new(std::fs::read_to_string(KV_FILE).ok().and_then(|json_string| serde_json::from_str(&json_string).ok()).unwrap_or_default()))
+}
+
+/// 写入（value 为 None 时删除）工作流 kv 中的键，未变化时返回 false。
+/// 单个工作流的 kv 不能超过 config.json 中 lua.kvMaxKeys 与 lua.kvMaxSizeKb 的限制，超出时保持原值
+fn update_kv(store: &mut HashMap<String, Map<String, Value>>, workflow_id: &str, key: String, value: Option<Value>) -> anyhow::Result<bool> {
+    let values = store.entry(workflow_id.to_string()).or_default();
+    if values.get(&key) == value.as_ref() {
+        return Ok(false);
+    }
+    let previous = match value {
+        Some(value) => values.insert(key.clone(), value),
+        None => values.remove(&key),
+    };
+    if let Err(e) = check_kv_limits(values) {
+        match previous {
+            Some(previous) => values.insert(key, previous),
+            None => values.remove(&key),
+        };
+        return Err(e);
+    }
+    if values.is_empty() {
+        store.remove(workflow_id);
+    }
+    Ok(true)
+}
+
+fn check_kv_limits(values: &Map<String, Value>) -> anyhow::Result<()> {
+    let limits = &CONFIG.lua;
+    if values.len() > limits.kv_max_keys {
+        bail!("kv 的键数超过限制 {}", limits.kv_max_keys);
+    }
+    let size = serde_json::to_vec(values)?.len();
+    if size > limits.kv_max_size_kb * 1024 {
+        bail!("kv 保存的数据超过限制 {} KB", limits.kv_max_size_kb);
+    }
+    Ok(())
+}
+
+/// 主机是否在 config.json 的 lua.allowedHosts 中，为空时不允许访问任何主机
+fn host_allowed(url: &Url) -> bool {
+    host_matches(url, &CONFIG.lua.allowed_hosts)
+}
+
+/// * 匹配任意主机，*.example.com 匹配其子域名；IP 形式的内网地址必须明确列出
+fn host_matches(url: &Url, allowed_hosts: &[String]) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase();
+    if listed(&host, allowed_hosts) {
+        return true;
+    }
+    if host.parse::<IpAddr>().is_ok_and(is_internal) {
+        return false;
+    }
+    allowed_hosts.iter().map(|pattern| pattern.trim().to_ascii_lowercase()).any(|pattern| match pattern.strip_prefix("*") {
+        Some("") => true,
+        Some(domain) => domain.starts_with('.') && host.ends_with(domain),
+        None => false,
+    })
+}
+
+/// 主机名或 IP 在 allowedHosts 中明确列出（不含通配符）
+fn listed(host: &str, allowed_hosts: &[String]) -> bool {
+    allowed_hosts.iter().any(|pattern| pattern.trim().trim_start_matches('[').trim_end_matches(']').eq_ignore_ascii_case(host))
+}
+
+/// 回环、私有网段、运营商 NAT、链路本地与未指定地址
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast() || (ip.octets()[0] == 100 && ip.octets()[1] & 0xC0 == 64),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_internal(IpAddr::V4(ip)),
+            None => ip.is_loopback() || ip.is_unspecified() || ip.is_unique_local() || ip.is_unicast_link_local(),
+        },
+    }
+}
+
+/// 解析域名时去掉内网地址，除非该主机在 allowedHosts 中明确列出，重定向与 DNS 重绑定同样无法访问内网
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_ascii_lowercase();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            let addrs: Vec<SocketAddr> = if listed(&host, &CONFIG.lua.allowed_hosts) { addrs } else { addrs.into_iter().filter(|addr| !is_internal(addr.ip())).collect() };
+            if addrs.is_empty() {
+                return Err(anyhow!("不允许访问内网地址 {}", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// 重定向同样检查主机是否允许
+fn client() -> &'static Client {
+    CLIENT.get_or_init(|| {
+        let policy = Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error(format!("重定向次数超过 {}", MAX_REDIRECTS))
+            } else if !host_allowed(attempt.url()) {
+                let message = format!("不允许重定向到 {}", attempt.url());
+                attempt.error(message)
+            } else {
+                attempt.follow()
+            }
+        });
+        Client::builder().redirect(policy).dns_resolver(Arc::new(PublicResolver)).build().expect("创建 HTTP 客户端失败")
+    })
+}
+
+/// options 支持 headers（表）与 timeout（秒）；body 为字符串时原样发送，表以 JSON 发送。
+/// 返回 {status, statusText, ok, url, headers, body}，与 http 节点的结构化响应相同
+async fn request(lua: &Lua, method: Method, url: &str, body: mlua::Value, options: Option<mlua::Table>) -> mlua::Result<mlua::Value> {
+    let builder = (|| -> anyhow::Result<_> {
+        if !CONFIG.lua.http_enabled {
+            bail!("未开启 Lua 脚本的 HTTP 请求，请在 config.json 中设置 lua.httpEnabled 与 lua.allowedHosts");
+        }
+        let parsed = Url::parse(url).map_err(|e| anyhow!("请求地址无效 {}: {}", url, e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            bail!("只支持 http:// 与 https:// 地址: {}", url);
+        }
+        if !host_allowed(&parsed) {
+            bail!("不允许访问 {}", parsed.host_str().unwrap_or_default());
+        }
+        let mut builder = client().request(method, parsed);
+        if let Some(options) = &options {
+            if let Some(headers) = options.get::<Option<HashMap<String, String>>>("headers")? {
+                for (name, value) in headers {
+                    builder = builder.header(name, value);
+                }
+            }
+            if let Some(timeout) = options.get::<Option<f64>>("timeout")? {
+                let timeout = Duration::try_from_secs_f64(timeout).map_err(|_| anyhow!("timeout 不是合法的秒数: {}", timeout))?;
+                builder = builder.timeout(timeout);
+            }
+        }
+        Ok(match &body {
+            mlua::Value::Nil => builder,
+            mlua::Value::String(s) => builder.body(s.as_bytes().to_vec()),
+            value => builder.json(&lua.from_value::<Value>(value.clone())?),
+        })
+    })()
+    .map_err(mlua::Error::external)?;
+    let response = builder.send().await.map_err(|e| mlua::Error::external(http::request_error(e)))?;
+    let value = http::response_value(response).await.map_err(mlua::Error::external)?;
+    lua.to_value(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn matches_allowed_hosts() {
+        let allowed = |url: &str, hosts: &[&str]| {
+            host_matches(
+                &Url::parse(url).unwrap(),
+                &hosts.iter().map(|host| host.to_string()).collect::<Vec<_>>(),
+            )
+        };
+        assert!(!allowed("https://example.com", &[]));
+        assert!(allowed("https://API.Example.com/x", &["*.example.com"]));
+        assert!(!allowed("https://example.com", &["*.example.com"]));
+        assert!(!allowed("https://badexample.com", &["*.example.com"]));
+        assert!(allowed("https://example.com", &["*"]));
+        for url in ["http://127.0.0.1", "http://10.1.2.3", "http://169.254.169.254/latest", "http://[::1]:8080", "http://[::ffff:192.168.1.1]", "http://100.64.0.1"] {
+            assert!(!allowed(url, &["*"]), "{}", url);
+        }
+        assert!(allowed("http://169.254.169.254/latest", &[
+            "169.254.169.254"
+        ]));
+        assert!(allowed("http://[::1]:8080", &["[::1]"]));
+        assert!(allowed("http://8.8.8.8", &["*"]));
+    }
+
+    #[tokio::test]
+    async fn resolver_drops_internal_addresses() {
+        let error = PublicResolver.resolve("localhost".parse().unwrap()).await.err().unwrap();
+        assert_eq!(error.to_string(), "不允许访问内网地址 localhost");
+    }
+
+    #[test]
+    fn limits_kv_keys_and_size() {
+        let mut store = HashMap::new();
+        for i in 0..CONFIG.lua.kv_max_keys {
+            assert!(update_kv(&mut store, "w", format!("k{}", i), Some(json!(i))).unwrap());
+        }
+        let error = update_kv(&mut store, "w", "extra".to_string(), Some(json!(1))).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("kv 的键数超过限制 {}", CONFIG.lua.kv_max_keys)
+        );
+        assert!(!store["w"].contains_key("extra"));
+        assert!(!update_kv(&mut store, "w", "k1".to_string(), Some(json!(1))).unwrap());
+        // 其他工作流不受影响
+        assert!(update_kv(&mut store, "other", "extra".to_string(), Some(json!(1))).unwrap());
+
+        let large = json!("x".repeat(CONFIG.lua.kv_max_size_kb * 1024));
+        let error = update_kv(&mut store, "w", "k1".to_string(), Some(large)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("kv 保存的数据超过限制 {} KB", CONFIG.lua.kv_max_size_kb)
+        );
+        assert_eq!(store["w"]["k1"], json!(1));
+
+        assert!(update_kv(&mut store, "other", "extra".to_string(), None).unwrap());
+        assert!(!store.contains_key("other"));
+    }
+}
@@ -1,61 +1,522 @@
-use std::convert::Infallible;
+use std::{
+    collections::HashMap, convert::Infallible, fmt, sync::{
+        Arc, Mutex, atomic::{AtomicU64, Ordering}
+    }, time::{Duration, Instant}
+};
 
-use axum::response::sse::Event;
+use anyhow::{anyhow, bail};
+use axum::{Json, response::sse::Event};
 use chrono::Utc;
 use log::info;
-use mlua::Lua;
+use mlua::{HookTriggers, Lua, LuaOptions, LuaSerdeExt, StdLib, VmState};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
 use tokio::sync::mpsc::UnboundedSender;
 
-use super::super::{
-    model::{Log, LogData, Node}, sse
+use super::{
+    super::{
+        context::Context, model::{Log, LogData, Node}, sse
+    }, lua_api::{self, Host}
 };
+use crate::{config::CONFIG, error::AppError};
 
-pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
-    let script = node.config.get("script").map(|v| v.to_string()).unwrap_or_default();
-    let lua = Lua::new();
+/// 脚本在错误信息与调用栈中的名称，显示为 script:行号
+const CHUNK_NAME: &str = "=script";
+const CHUNK_PREFIX: &str = "script:";
+/// 每执行这么多条指令检查一次超时与指令数
+const HOOK_INSTRUCTIONS: u32 = 1000;
+/// 钩子能够检查时优先由钩子报告超时（带行号），超过该时长仍未结束时直接判定超时
+const TIMEOUT_GRACE: Duration = Duration::from_millis(500);
+/// 基础库中可以读取文件或加载字节码的函数
+const UNSAFE_GLOBALS: [&str; 4] = ["dofile", "loadfile", "load", "collectgarbage"];
+/// os 库中保留的函数，其余（execute、remove、getenv、exit 等）不可用
+const SAFE_OS_FUNCTIONS: [&str; 4] = ["time", "clock", "date", "difftime"];
 
-    info!("Lua script: {}", script);
+/// 受限的 Lua 环境：只加载 string、table、math、utf8 与部分 os 函数，不可访问文件、进程和模块加载；
+/// 不提供 coroutine：mlua 的钩子只作用于设置它的线程，协程中的代码不受限制；
+/// 按 config.json 中的 lua 配置限制执行时间、指令数与内存。超出限制时返回的描述写入 violation。
+/// 执行时间与指令数只在 Lua 指令之间检查，string.find 等 C 函数的单次调用期间不受检查
+struct Sandbox {
+    lua: Lua,
+    timeout: Duration,
+    deadline: Instant,
+    violation: Arc<Mutex<Option<String>>>,
+}
 
-    // 👇 注入 json.lua（纯 Lua 实现）
-    let json_lua = include_str!("json.lua"); // 假设你把 json.lua 放在同一目录
-    // if let Err(err) = lua.load(json_lua).eval::<()>() {
-    //     info!("Failed to load json.lua: {}", err);
-    // }
-    let json_module: mlua::Table = match lua.load(json_lua).eval() {
-        Ok(module) => module,
-        Err(err) => {
-            info!("Failed to load json.lua: {}", err);
-            return Err(err.into());
+impl Sandbox {
+    fn new(node: &Node) -> anyhow::Result<Self> {
+        let lua = Lua::new_with(
+            StdLib::STRING | StdLib::TABLE | StdLib::MATH | StdLib::UTF8 | StdLib::OS,
+            LuaOptions::default(),
+        )?;
+        let globals = lua.globals();
+        for name in UNSAFE_GLOBALS {
+            globals.raw_remove(name)?;
         }
-    };
-    lua.globals().set("json", json_module)?;
-
-    // 👇 注入用户脚本并执行
-    // let result: mlua::Value = lua.load(script).eval()?;
-    let result: mlua::Value = match lua.load(script).eval() {
-        Ok(value) => value,
-        Err(err) => {
-            info!("Failed to execute Lua script: {}", err);
-            mlua::Value::Nil
+        let os: mlua::Table = globals.get("os")?;
+        let safe_os = lua.create_table()?;
+        for name in SAFE_OS_FUNCTIONS {
+            safe_os.set(name, os.get::<mlua::Value>(name)?)?;
+        }
+        globals.set("os", safe_os)?;
+
+        lua.set_memory_limit(CONFIG.lua.memory_limit_mb * 1024 * 1024)?;
+
+        let timeout = node
+            .config
+            .get("timeout")
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|v| *v > 0.0)
+            .map(Duration::from_secs_f64)
+            .unwrap_or(Duration::MAX)
+            .min(Duration::from_secs(CONFIG.lua.timeout_secs));
+        Ok(Sandbox { lua, timeout, deadline: Instant::now() + timeout, violation: Arc::new(Mutex::new(None)) })
+    }
+
+    fn timeout_message(&self) -> String {
+        format!("Lua 脚本执行超时（{} 秒）", self.timeout.as_secs_f64())
+    }
+
+    /// 在执行脚本的线程上设置钩子，每执行一批指令检查超时与指令数
+    fn limit(&self, thread: &mlua::Thread) {
+        let max_instructions = CONFIG.lua.max_instructions;
+        let instructions = AtomicU64::new(0);
+        let deadline = self.deadline;
+        let timeout_message = self.timeout_message();
+        let violation = self.violation.clone();
+        thread.set_hook(
+            HookTriggers::new().every_nth_instruction(HOOK_INSTRUCTIONS),
+            move |_, debug| {
+                let count = instructions.fetch_add(HOOK_INSTRUCTIONS as u64, Ordering::Relaxed) + HOOK_INSTRUCTIONS as u64;
+                let message = if Instant::now() > deadline {
+                    timeout_message.clone()
+                } else if max_instructions > 0 && count > max_instructions {
+                    format!("Lua 脚本执行的指令数超过限制 {}", max_instructions)
+                } else {
+                    return Ok(VmState::Continue);
+                };
+                let message = format!("{}（第 {} 行）", message, debug.curr_line());
+                *violation.lock().unwrap() = Some(message.clone());
+                Err(mlua::Error::RuntimeError(message))
+            },
+        );
+    }
+
+    /// 在受限的线程中执行脚本并转换返回值；等待宿主函数（http、sleep 等）时让出运行时，整体不超过超时时间
+    async fn run(&self, script: &str) -> anyhow::Result<String> {
+        let running = async {
+            let function = self.lua.load(script).set_name(CHUNK_NAME).into_function()?;
+            let thread = self.lua.create_thread(function)?;
+            self.limit(&thread);
+            thread.into_async::<mlua::Value>(()).await
+        };
+        let result = tokio::time::timeout_at(self.deadline.into(), running).await.map_err(|_| anyhow!(self.timeout_message()))?;
+        // 超出沙箱限制时节点失败，即使脚本用 pcall 捕获了错误
+        if let Some(message) = self.violation.lock().unwrap().take() {
+            bail!(message);
         }
-    };
+        let result = match result {
+            Ok(value) => value,
+            Err(err) => {
+                if is_memory_error(&err) {
+                    bail!(
+                        "Lua 脚本使用的内存超过限制 {} MB",
+                        CONFIG.lua.memory_limit_mb
+                    );
+                }
+                bail!("Lua 脚本执行失败，{}", ScriptError::new(&err, script));
+            }
+        };
+        Ok(match result {
+            mlua::Value::String(s) => s.to_str()?.to_string(),
+            mlua::Value::Nil => "null".to_string(),
+            mlua::Value::Boolean(b) => b.to_string(),
+            mlua::Value::Integer(n) => n.to_string(),
+            mlua::Value::Number(n) => n.to_string(),
+            value => to_json(&self.lua, value).map_err(|e| anyhow!("Lua 脚本的返回值无法转换为 JSON: {}", e))?.to_string(),
+        })
+    }
+}
+
+/// 内存不足的错误可能被包装在回调错误中
+fn is_memory_error(err: &mlua::Error) -> bool {
+    match err {
+        mlua::Error::MemoryError(_) => true,
+        mlua::Error::CallbackError { cause, .. } => is_memory_error(cause),
+        _ => false,
+    }
+}
+
+/// 脚本错误：错误信息、行号、列号（仅语法错误可以确定）与调用栈
+#[derive(Serialize, Debug)]
+pub struct ScriptError {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub traceback: Option<String>,
+}
+
+impl ScriptError {
+    pub fn new(err: &mlua::Error, script: &str) -> Self {
+        let detail = match err {
+            mlua::Error::SyntaxError { message, .. } => message.clone(),
+            mlua::Error::RuntimeError(message) => message.clone(),
+            // Rust 函数返回的错误，调用栈在外层
+            mlua::Error::CallbackError { cause, traceback } => format!("{}\n{}", cause, traceback),
+            other => other.to_string(),
+        };
+        let (message, traceback) = match detail.split_once("\nstack traceback:") {
+            Some((message, traceback)) => (
+                message.to_string(),
+                Some(format!("stack traceback:{}", traceback)),
+            ),
+            None => (detail, None),
+        };
+        // 错误信息或调用栈中第一个 script:行号 即出错位置
+        let line = [Some(&message), traceback.as_ref()].into_iter().flatten().find_map(|text| {
+            text.match_indices(CHUNK_PREFIX).find_map(|(index, _)| {
+                let rest = &text[index + CHUNK_PREFIX.len()..];
+                let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+                rest[..digits].parse::<usize>().ok()
+            })
+        });
+        let message = match line {
+            Some(line) => message.replacen(&format!("{}{}: ", CHUNK_PREFIX, line), "", 1),
+            None => message,
+        };
+        // 语法错误形如 "unexpected symbol near 'x'"，按该符号在行内最后出现的位置确定列号
+        let column = match (err, line) {
+            (mlua::Error::SyntaxError { .. }, Some(line)) => message.rsplit_once(" near ").and_then(|(_, near)| {
+                let text = script.lines().nth(line - 1).unwrap_or_default();
+                match near.trim_matches('\'') {
+                    "<eof>" => Some(text.chars().count() + 1),
+                    token => text.rfind(token).map(|index| text[..index].chars().count() + 1),
+                }
+            }),
+            _ => None,
+        };
+        ScriptError { message, line, column, traceback }
+    }
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "第 {} 行第 {} 列: ", line, column)?,
+            (Some(line), None) => write!(f, "第 {} 行: ", line)?,
+            _ => {}
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(traceback) = &self.traceback {
+            write!(f, "\n{}", traceback)?;
+        }
+        Ok(())
+    }
+}
+
+/// 节点输出为 JSON 时转换为 Lua 值，否则作为字符串
+fn output_value(output: &str) -> Value {
+    serde_json::from_str(output).unwrap_or_else(|_| Value::String(output.to_string()))
+}
+
+/// Lua 值转换为 JSON：json.null 转换为 null，包含函数等无法转换的值时报错
+fn to_json(lua: &Lua, value: mlua::Value) -> mlua::Result<Value> {
+    lua.from_value(value)
+}
+
+/// 注入脚本可用的全局变量：input（第一个输入）、inputs（全部输入）、nodes（已执行节点的输出，按节点 id）、
+/// trigger（触发事件，手动执行时为 nil）、execution（执行 id、工作流 id、节点 id）、vars、params，
+/// 以及 json.encode / json.decode。JSON 值直接转换为 Lua 表，null 为 json.null
+fn set_globals(lua: &Lua, node: &Node, inputs: &[String], ctx: &Context) -> mlua::Result<()> {
+    let globals = lua.globals();
+    let inputs: Vec<Value> = inputs.iter().map(|input| output_value(input)).collect();
+    match inputs.first() {
+        Some(input) => globals.set("input", lua.to_value(input)?)?,
+        None => globals.set("input", mlua::Value::Nil)?,
+    }
+    globals.set("inputs", lua.to_value(&inputs)?)?;
+    let outputs: HashMap<String, Value> = ctx.outputs().iter().map(|(node_id, output)| (node_id.clone(), output_value(output))).collect();
+    globals.set("nodes", lua.to_value(&outputs)?)?;
+    if let Some(event) = &ctx.trigger {
+        globals.set(
+            "trigger",
+            lua.to_value(&json!({ "nodeId": event.node_id, "data": event.data }))?,
+        )?;
+    }
+    globals.set(
+        "execution",
+        lua.to_value(&json!({ "id": ctx.execution_id, "workflowId": ctx.workflow_id, "nodeId": node.id }))?,
+    )?;
+    globals.set("vars", lua.to_value(&ctx.variables())?)?;
+    globals.set("params", lua.to_value(&ctx.params)?)?;
+
+    let json = lua.create_table()?;
+    json.set("null", lua.null())?;
+    json.set(
+        "encode",
+        lua.create_function(|lua, value: mlua::Value| serde_json::to_string(&to_json(lua, value)?).map_err(mlua::Error::external))?,
+    )?;
+    json.set(
+        "decode",
+        lua.create_function(|lua, text: String| lua.to_value(&serde_json::from_str::<Value>(&text).map_err(mlua::Error::external)?))?,
+    )?;
+    globals.set("json", json)?;
+    Ok(())
+}
+
+/// 执行 Lua 脚本，可以调用 lua_api 中的宿主函数。返回字符串时原样输出，表等其他值转换为 JSON 输出
+pub async fn execute(node: &Node, inputs: &[String], ctx: &Context, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
+    let script = node.config.get("script").map(|v| v.to_string()).unwrap_or_default();
+    let sandbox = Sandbox::new(node)?;
+    let lua = &sandbox.lua;
 
-    // 安全转换为字符串
-    let result_str = match result {
-        mlua::Value::String(s) => s.to_str()?.to_string(),
-        mlua::Value::Nil => "null".to_string(),
-        mlua::Value::Boolean(b) => b.to_string(),
-        mlua::Value::Number(n) => n.to_string(),
-        _ => format!("<unsupported: {:?}>", result),
-    };
+    info!("Lua script: {}", script);
+
+    set_globals(lua, node, inputs, ctx)?;
+    let logs = Arc::new(Mutex::new(vec![]));
+    let host_sender = Arc::new(Mutex::new(sender.clone()));
+    lua_api::register(lua, Host {
+        node_id: node.id.clone(),
+        workflow_id: ctx.workflow_id.clone(),
+        sender: host_sender.clone(),
+        logs: logs.clone(),
+    })?;
+    // 注册异步宿主函数时 mlua 会加载 coroutine 库，注册后从脚本环境中移除
+    lua.globals().raw_remove("coroutine")?;
+
+    // 钩子按指令检查限制，无法打断单次耗时的 C 函数调用（如复杂的模式匹配），因此在阻塞线程中执行，
+    // 超时后节点立即失败，但该调用会在后台继续直到结束
+    let deadline = sandbox.deadline + TIMEOUT_GRACE;
+    let timeout_message = sandbox.timeout_message();
+    let handle = tokio::runtime::Handle::current();
+    let running = tokio::task::spawn_blocking(move || handle.block_on(sandbox.run(&script)));
+    let result = tokio::time::timeout_at(deadline.into(), running).await;
+    host_sender.lock().unwrap().take();
+    let result_str = result.map_err(|_| anyhow!(timeout_message))???;
 
     info!("Lua script result: {}", result_str);
 
     let log_data = LogData { kind: "output".to_string(), data: Some(result_str.clone()), node_id: node.id.clone(), node_type: None, result: Some(result_str.clone()) };
     sse::send_json(log_data.clone(), sender)?;
 
-    Ok((
-        vec![Log { timestamp: Utc::now(), data: log_data }],
-        result_str,
+    let mut logs = std::mem::take(&mut *logs.lock().unwrap());
+    logs.push(Log { timestamp: Utc::now(), data: log_data });
+    Ok((logs, result_str))
+}
+
+#[derive(Deserialize)]
+pub struct CheckReqParam {
+    pub script: String,
+}
+
+/// 检查脚本语法而不执行，供编辑器使用：{"ok": true} 或 {"ok": false, "error": {message, line, column}}
+pub async fn check(Json(param): Json<CheckReqParam>) -> Result<Json<Value>, AppError> {
+    let lua = Lua::new_with(StdLib::NONE, LuaOptions::default()).map_err(|e| AppError::Internal(e.into()))?;
+    Ok(Json(
+        match lua.load(&param.script).set_name(CHUNK_NAME).into_function() {
+            Ok(_) => json!({ "ok": true }),
+            Err(err) => json!({ "ok": false, "error": ScriptError::new(&err, &param.script) }),
+        },
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::*;
+
+    async fn run(script: &str, inputs: &[&str], timeout: Option<&str>) -> anyhow::Result<String> {
+        let mut config = json!({ "script": script });
+        if let Some(timeout) = timeout {
+            config["timeout"] = json!(timeout);
+        }
+        let node: Node = serde_json::from_value(json!({ "id": "lua", "type": "lua-script", "position": { "x": 0, "y": 0 }, "config": config })).unwrap();
+        let ctx = Context::new(
+            "w".to_string(),
+            HashMap::from([("limit".to_string(), "10".to_string())]),
+        );
+        execute(
+            &node,
+            &inputs.iter().map(|input| input.to_string()).collect::<Vec<_>>(),
+            &ctx,
+            &None,
+        )
+        .await
+        .map(|(_, output)| output)
+    }
+
+    #[tokio::test]
+    async fn removes_unsafe_libraries() {
+        let script = "return tostring(io) .. tostring(require) .. tostring(load) .. tostring(os.execute) .. tostring(os.getenv) .. tostring(coroutine)";
+        assert_eq!(run(script, &[], None).await.unwrap(), "nilnilnilnilnilnil");
+        assert_eq!(
+            run("return type(os.time()) .. type(string.upper)", &[], None).await.unwrap(),
+            "numberfunction"
+        );
+    }
+
+    #[tokio::test]
+    async fn converts_results_and_reads_params() {
+        assert_eq!(
+            run("return params.limit .. '!'", &[], None).await.unwrap(),
+            "10!"
+        );
+        assert_eq!(
+            run("return params.limit / 4", &[], None).await.unwrap(),
+            "2.5"
+        );
+        assert_eq!(
+            run("return json.encode({a = 1})", &[], None).await.unwrap(),
+            r#"{"a":1}"#
+        );
+        assert_eq!(run("return nil", &[], None).await.unwrap(), "null");
+        assert_eq!(run("return 1 < 2", &[], None).await.unwrap(), "true");
+    }
+
+    #[tokio::test]
+    async fn times_out_even_inside_pcall() {
+        let error = run(
+            "pcall(function() while true do end end) return 1",
+            &[],
+            Some("0.2"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.to_string(), "Lua 脚本执行超时（0.2 秒）（第 1 行）");
+    }
+
+    #[tokio::test]
+    async fn limits_memory() {
+        let script = "local t = {} for i = 1, 1e8 do t[i] = string.rep('x', 64) .. i end return #t";
+        let error = run(script, &[], None).await.unwrap_err();
+        assert_eq!(error.to_string(), "Lua 脚本使用的内存超过限制 64 MB");
+    }
+
+    #[tokio::test]
+    async fn exposes_inputs_as_native_values() {
+        let inputs = [r#"{"items":[1,2,3],"name":"a","empty":null}"#, "plain text"];
+        let script = "return #input.items + #inputs .. input.name .. inputs[2] .. tostring(input.empty == json.null)";
+        assert_eq!(
+            run(script, &inputs, None).await.unwrap(),
+            "5aplain texttrue"
+        );
+        assert_eq!(
+            run("return execution.nodeId .. tostring(trigger)", &[], None).await.unwrap(),
+            "luanil"
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_tables_as_json() {
+        let output = run(
+            "return { n = input.n + 1, list = { 1, 2 }, none = json.null }",
+            &[r#"{"n":1}"#],
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&output).unwrap(),
+            json!({ "n": 2, "list": [1, 2], "none": null })
+        );
+        assert_eq!(
+            run("return json.decode('[1,2]')[2] * 10", &[], None).await.unwrap(),
+            "20"
+        );
+        let error = run("return { f = print }", &[], None).await.unwrap_err().to_string();
+        assert!(
+            error.starts_with("Lua 脚本的返回值无法转换为 JSON"),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_with_line_and_traceback() {
+        let error = run(
+            "local a = 1\nlocal function f() error('boom') end\nf()",
+            &[],
+            None,
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(
+            error.starts_with("Lua 脚本执行失败，第 2 行: boom\nstack traceback:"),
+            "{}",
+            error
+        );
+        let error = run("return nil + 1", &[], None).await.unwrap_err().to_string();
+        assert!(
+            error.starts_with("Lua 脚本执行失败，第 1 行: attempt to perform arithmetic on a nil value"),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn checks_syntax_without_running() {
+        let check = |script: &str| check(Json(CheckReqParam { script: script.to_string() }));
+        assert_eq!(
+            check("while true do end").await.unwrap().0,
+            json!({ "ok": true })
+        );
+        let Json(result) = check("local a = 1\nlocal b = = 2").await.unwrap();
+        assert_eq!(result["ok"], json!(false));
+        assert_eq!(
+            (&result["error"]["line"], &result["error"]["column"]),
+            (&json!(2), &json!(11))
+        );
+        assert_eq!(
+            result["error"]["message"],
+            json!("unexpected symbol near '='")
+        );
+    }
+
+    #[tokio::test]
+    async fn calls_host_functions() {
+        let script = "sleep(0.01) log.info('n', 1) return base64.decode(base64.encode('abc')) .. hash.sha256('') .. #uuid()";
+        assert_eq!(
+            run(script, &[], None).await.unwrap(),
+            "abce3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b85536"
+        );
+        let error = run("sleep(5) return 1", &[], Some("0.2")).await.unwrap_err();
+        assert_eq!(error.to_string(), "Lua 脚本执行超时（0.2 秒）");
+    }
+
+    #[tokio::test]
+    async fn stops_at_instruction_limit() {
+        let error = run(
+            "local n = 0\nfor i = 1, 1e12 do n = n + i end\nreturn n",
+            &[],
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Lua 脚本执行的指令数超过限制 {}（第 2 行）",
+                CONFIG.lua.max_instructions
+            )
+        );
+    }
+
+    /// 单次耗时数秒的 C 函数调用无法被钩子打断，节点仍然在超时后失败（测试结束时等待该调用完成）
+    #[tokio::test]
+    async fn times_out_during_long_c_calls() {
+        let started = Instant::now();
+        let error = run(
+            "return string.rep('a', 1200):find('.-.-x')",
+            &[],
+            Some("0.2"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.to_string(), "Lua 脚本执行超时（0.2 秒）");
+        assert!(started.elapsed() < Duration::from_millis(1500));
+    }
+}
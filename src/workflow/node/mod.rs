@@ -1,9 +1,24 @@
+pub mod approval;
 pub mod condition;
+pub mod convert;
+pub mod email;
+pub mod exec;
+pub mod graphql;
 pub mod http;
 pub mod input;
 pub mod llm;
+pub mod lua_api;
 pub mod lua_script;
+pub mod mqtt;
+pub mod mysql;
 pub mod output;
 pub mod postgresql;
 pub mod read_file;
+pub mod redis;
+pub mod set_variables;
+pub mod sql;
+pub mod sqlite;
+pub mod transform;
+pub mod trigger;
+pub mod websocket;
 pub mod write_file;
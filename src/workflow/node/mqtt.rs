@@ -0,0 +1,394 @@
+use std::{
+    collections::HashMap, convert::Infallible, sync::{
+        Arc, Mutex, OnceLock, atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering}
+    }, time::Duration
+};
+
+use anyhow::{anyhow, bail};
+use axum::response::sse::Event;
+use chrono::Utc;
+use log::{info, warn};
+use reqwest::Url;
+use rumqttc::{AsyncClient, Event as MqttEvent, EventLoop, MqttOptions, Packet, Publish, QoS, TlsConfiguration, Transport};
+use serde_json::{Value, json};
+use tokio::{
+    sync::{mpsc, watch}, task::AbortHandle
+};
+
+use super::super::{
+    binary::Binary, model::{Log, LogData, Node}, sse, tls
+};
+
+static BROKERS: OnceLock<Mutex<HashMap<String, Arc<Broker>>>> = OnceLock::new();
+static DEFAULT_URL: &str = "mqtt://127.0.0.1:1883";
+const MAX_PACKET_SIZE: usize = 16 * 1024 * 1024;
+const MIN_BACKOFF_SECS: u64 = 1;
+const MAX_BACKOFF_SECS: u64 = 60;
+/// 只用于发布的连接空闲这么久后关闭
+const PUBLISH_IDLE_SECS: u64 = 60;
+
+/// 到 MQTT 服务器的连接，mqtt 节点与 mqtt-trigger 按地址和账号共享。
+/// 后台任务驱动事件循环：断开后按 1 秒起、每次翻倍、最长 60 秒的间隔重连，重连后重新订阅；
+/// 最后一个订阅取消、或只用于发布的连接空闲一段时间后关闭连接并结束后台任务
+pub struct Broker {
+    key: String,
+    url: String,
+    client: AsyncClient,
+    task: Mutex<Option<AbortHandle>>,
+    released: AtomicBool,
+    /// 正在发布消息的节点数
+    publishers: AtomicUsize,
+    connected: watch::Receiver<bool>,
+    last_error: Mutex<Option<String>>,
+    subscribers: Mutex<Vec<Subscriber>>,
+    next_id: AtomicU64,
+}
+
+struct Subscriber {
+    id: u64,
+    filters: Vec<String>,
+    qos: QoS,
+    sender: mpsc::UnboundedSender<Publish>,
+}
+
+/// 订阅，收到的消息从 messages 读取，丢弃时取消不再被其他订阅使用的主题
+pub struct Subscription {
+    broker: Arc<Broker>,
+    id: u64,
+    pub messages: mpsc::UnboundedReceiver<Publish>,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let mut subscribers = self.broker.subscribers.lock().unwrap();
+        let Some(index) = subscribers.iter().position(|subscriber| subscriber.id == self.id) else {
+            return;
+        };
+        let subscriber = subscribers.remove(index);
+        for filter in subscriber.filters {
+            if !subscribers.iter().any(|other| other.filters.contains(&filter)) {
+                let _ = self.broker.client.try_unsubscribe(filter);
+            }
+        }
+        let idle = subscribers.is_empty();
+        drop(subscribers);
+        if idle {
+            self.broker.release();
+        }
+    }
+}
+
+/// 按 url、username、password 获取共享连接。url 形如 mqtt://host:1883 或 mqtts://host:8883，
+/// 也可以在 url 中带用户名和密码
+pub fn broker(node: &Node) -> anyhow::Result<Arc<Broker>> {
+    let url = node.config.get("url").map(|v| v.trim()).filter(|v| !v.is_empty()).unwrap_or(DEFAULT_URL);
+    let username = node.config.get("username").map(|v| v.trim()).unwrap_or_default();
+    let password = node.config.get("password").map(|v| v.as_str()).unwrap_or_default();
+    let key = format!("{}\n{}\n{}", url, username, password);
+    let mut brokers = BROKERS.get_or_init(Default::default).lock().unwrap();
+    if let Some(broker) = brokers.get(&key) {
+        return Ok(broker.clone());
+    }
+
+    let parsed = Url::parse(url).map_err(|e| anyhow!("MQTT 地址无效 {}: {}", url, e))?;
+    let tls = match parsed.scheme() {
+        "mqtt" | "tcp" => false,
+        "mqtts" | "ssl" => true,
+        scheme => bail!("不支持的 MQTT 协议 {}，应为 mqtt:// 或 mqtts://", scheme),
+    };
+    let host = parsed.host_str().ok_or_else(|| anyhow!("MQTT 地址缺少主机: {}", url))?;
+    let port = parsed.port().unwrap_or(if tls { 8883 } else { 1883 });
+    let client_id = format!("n2s-{}", uuid::Uuid::new_v4().simple());
+    let mut options = MqttOptions::new(client_id, host, port);
+    options.set_keep_alive(Duration::from_secs(30)).set_max_packet_size(MAX_PACKET_SIZE, MAX_PACKET_SIZE);
+    match (username, parsed.username()) {
+        ("", "") => {}
+        ("", user) => {
+            options.set_credentials(user, parsed.password().unwrap_or_default());
+        }
+        (user, _) => {
+            options.set_credentials(user, password);
+        }
+    }
+    if tls {
+        let config = tls::client_config(None)?;
+        options.set_transport(Transport::Tls(TlsConfiguration::Rustls(Arc::new(config))));
+    }
+
+    let (client, eventloop) = AsyncClient::new(options, 100);
+    let (connected_sender, connected) = watch::channel(false);
+    let broker = Arc::new(Broker {
+        key: key.clone(),
+        url: url.to_string(),
+        client,
+        task: Mutex::new(None),
+        released: AtomicBool::new(false),
+        publishers: AtomicUsize::new(0),
+        connected,
+        last_error: Mutex::new(None),
+        subscribers: Mutex::new(vec![]),
+        next_id: AtomicU64::new(0),
+    });
+    let task = tokio::spawn(run(broker.clone(), eventloop, connected_sender));
+    *broker.task.lock().unwrap() = Some(task.abort_handle());
+    brokers.insert(key, broker.clone());
+    Ok(broker)
+}
+
+async fn run(broker: Arc<Broker>, mut eventloop: EventLoop, connected: watch::Sender<bool>) {
+    let mut backoff = MIN_BACKOFF_SECS;
+    loop {
+        match eventloop.poll().await {
+            Ok(MqttEvent::Incoming(Packet::ConnAck(_))) => {
+                info!("已连接 MQTT 服务器 {}", broker.url);
+                backoff = MIN_BACKOFF_SECS;
+                *broker.last_error.lock().unwrap() = None;
+                connected.send_replace(true);
+                // 不保留会话，重连后需要重新订阅
+                let subscribers = broker.subscribers.lock().unwrap();
+                for subscriber in subscribers.iter() {
+                    for filter in &subscriber.filters {
+                        if let Err(e) = broker.client.try_subscribe(filter, subscriber.qos) {
+                            warn!("MQTT 订阅 {} 失败: {}", filter, e);
+                        }
+                    }
+                }
+            }
+            Ok(MqttEvent::Incoming(Packet::Publish(publish))) => {
+                let subscribers = broker.subscribers.lock().unwrap();
+                for subscriber in subscribers.iter().filter(|subscriber| subscriber.filters.iter().any(|filter| rumqttc::matches(&publish.topic, filter))) {
+                    let _ = subscriber.sender.send(publish.clone());
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                warn!(
+                    "MQTT 服务器 {} 连接出错: {}，{} 秒后重连",
+                    broker.url, e, backoff
+                );
+                *broker.last_error.lock().unwrap() = Some(e.to_string());
+                connected.send_replace(false);
+                tokio::time::sleep(Duration::from_secs(backoff)).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF_SECS);
+            }
+        }
+    }
+}
+
+impl Broker {
+    /// 订阅主题（支持 + 与 # 通配符）
+    pub async fn subscribe(self: &Arc<Self>, filters: Vec<String>, qos: QoS) -> anyhow::Result<Subscription> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, messages) = mpsc::unbounded_channel();
+        self.subscribers.lock().unwrap().push(Subscriber { id, filters: filters.clone(), qos, sender });
+        let subscription = Subscription { broker: self.clone(), id, messages };
+        // 获取连接后、订阅前连接已被关闭，由调用方重新获取连接
+        if self.released.load(Ordering::SeqCst) {
+            bail!("MQTT 服务器 {} 的连接已关闭", self.url);
+        }
+        for filter in filters {
+            self.client.subscribe(filter, qos).await?;
+        }
+        Ok(subscription)
+    }
+
+    /// 没有订阅也没有正在发布的消息时从共享连接中移除并结束后台任务；之后再使用会重新建立连接
+    fn release(self: &Arc<Self>) {
+        let mut brokers = BROKERS.get_or_init(Default::default).lock().unwrap();
+        if !self.subscribers.lock().unwrap().is_empty() || self.publishers.load(Ordering::SeqCst) > 0 || self.released.load(Ordering::SeqCst) {
+            return;
+        }
+        if brokers.get(&self.key).is_some_and(|broker| Arc::ptr_eq(broker, self)) {
+            brokers.remove(&self.key);
+        }
+        self.released.store(true, Ordering::SeqCst);
+        // 结束事件循环即关闭连接
+        if let Some(task) = self.task.lock().unwrap().take() {
+            task.abort();
+        }
+        info!("已断开 MQTT 服务器 {}", self.url);
+    }
+
+    /// 等待连接建立，超时时返回最近一次连接错误
+    async fn wait_connected(&self, timeout: Duration) -> anyhow::Result<()> {
+        let mut connected = self.connected.clone();
+        if tokio::time::timeout(timeout, connected.wait_for(|connected| *connected)).await.is_err() {
+            let last_error = self.last_error.lock().unwrap().clone().unwrap_or("连接超时".to_string());
+            bail!("连接 MQTT 服务器 {} 失败: {}", self.url, last_error);
+        }
+        Ok(())
+    }
+}
+
+/// 发布消息期间持有的连接。最后一个发布结束后，连接空闲 60 秒且没有订阅时关闭
+struct Publishing(Arc<Broker>);
+
+impl Publishing {
+    fn new(node: &Node) -> anyhow::Result<Self> {
+        loop {
+            let broker = broker(node)?;
+            broker.publishers.fetch_add(1, Ordering::SeqCst);
+            // 计数前连接已被关闭时重新获取
+            if !broker.released.load(Ordering::SeqCst) {
+                return Ok(Publishing(broker));
+            }
+            broker.publishers.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl Drop for Publishing {
+    fn drop(&mut self) {
+        if self.0.publishers.fetch_sub(1, Ordering::SeqCst) == 1 {
+            let broker = self.0.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(PUBLISH_IDLE_SECS)).await;
+                broker.release();
+            });
+        }
+    }
+}
+
+/// qos 配置为 0、1、2，默认 0
+pub fn qos(node: &Node) -> anyhow::Result<QoS> {
+    match node.config.get("qos").map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        Some(v) => v.parse::<u8>().ok().and_then(|v| rumqttc::qos(v).ok()).ok_or_else(|| anyhow!("qos 应为 0、1 或 2: {}", v)),
+        None => Ok(QoS::AtMostOnce),
+    }
+}
+
+/// 消息内容：JSON 文本解析为 JSON，其余文本为字符串，非 UTF-8 内容保存为二进制数据
+pub fn payload_value(payload: &[u8]) -> anyhow::Result<Value> {
+    Ok(match std::str::from_utf8(payload) {
+        Ok(text) => serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string())),
+        Err(_) => serde_json::to_value(Binary::save(payload, None, None)?)?,
+    })
+}
+
+/// 发布消息到 topic：payload 为消息内容（二进制引用时发送其内容），qos 为 0、1、2，retain 为保留消息。
+/// 连接不可用时等待 timeout 秒（默认 30）；消息进入发送队列后即完成，QoS 1/2 的确认与重发由连接负责
+pub async fn execute(node: &Node, sender: &Option<mpsc::UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
+    let mut logs = vec![];
+    let topic = node.config.get("topic").map(|v| v.trim()).filter(|v| !v.is_empty()).ok_or_else(|| anyhow!("topic 为空"))?;
+    if topic.contains(['+', '#']) {
+        bail!("发布的 topic 不能包含通配符: {}", topic);
+    }
+    let payload = node.config.get("payload").cloned().unwrap_or_default();
+    let payload = match Binary::parse(&payload) {
+        Some(binary) => binary.read()?,
+        None => payload.into_bytes(),
+    };
+    let qos = qos(node)?;
+    let retain = node.config.get("retain").is_some_and(|v| v.trim() == "true");
+    let timeout = node.config.get("timeout").and_then(|v| v.trim().parse::<u64>().ok()).unwrap_or(30);
+
+    let publishing = Publishing::new(node)?;
+    let broker = &publishing.0;
+    let log_data = LogData {
+        kind: "mqtt-info".to_string(),
+        data: Some(format!(
+            "发布消息到 {} 的 {}（{} 字节）",
+            broker.url,
+            topic,
+            payload.len()
+        )),
+        node_id: node.id.clone(),
+        node_type: None,
+        result: None,
+    };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    sse::send_json(log_data, sender)?;
+
+    broker.wait_connected(Duration::from_secs(timeout)).await?;
+    let size = payload.len();
+    broker.client.publish(topic, qos, retain, payload).await.map_err(|e| anyhow!("发布消息失败: {}", e))?;
+
+    let output = json!({ "topic": topic, "qos": qos as u8, "retain": retain, "size": size }).to_string();
+    let log_data = LogData { kind: "output".to_string(), data: Some(output.clone()), node_id: node.id.clone(), node_type: None, result: Some(output.clone()) };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    sse::send_json(log_data, sender)?;
+
+    Ok((logs, output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(config: Value) -> Node {
+        serde_json::from_value(json!({ "id": "mqtt", "type": "mqtt", "position": { "x": 0, "y": 0 }, "config": config })).unwrap()
+    }
+
+    #[test]
+    fn parses_qos() {
+        assert_eq!(qos(&node(json!({}))).unwrap(), QoS::AtMostOnce);
+        assert_eq!(
+            qos(&node(json!({ "qos": " 2 " }))).unwrap(),
+            QoS::ExactlyOnce
+        );
+        assert_eq!(
+            qos(&node(json!({ "qos": "3" }))).unwrap_err().to_string(),
+            "qos 应为 0、1 或 2: 3"
+        );
+    }
+
+    #[test]
+    fn decodes_payloads() {
+        assert_eq!(payload_value(br#"{"a":1}"#).unwrap(), json!({ "a": 1 }));
+        assert_eq!(payload_value(b"on").unwrap(), json!("on"));
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_config() {
+        let cases = [
+            (json!({}), "topic 为空"),
+            (
+                json!({ "topic": "a/+/b" }),
+                "发布的 topic 不能包含通配符: a/+/b",
+            ),
+            (json!({ "topic": "a", "qos": "x" }), "qos 应为 0、1 或 2: x"),
+            (
+                json!({ "topic": "a", "url": "http://127.0.0.1" }),
+                "不支持的 MQTT 协议 http，应为 mqtt:// 或 mqtts://",
+            ),
+        ];
+        for (config, message) in cases {
+            assert_eq!(
+                execute(&node(config), &None).await.unwrap_err().to_string(),
+                message
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn releases_publish_only_brokers() {
+        let node = node(json!({ "url": "mqtt://127.0.0.1:1", "topic": "n2s/test" }));
+        let shared = |broker: &Arc<Broker>| BROKERS.get().unwrap().lock().unwrap().get(&broker.key).is_some_and(|shared| Arc::ptr_eq(shared, broker));
+        let publishing = Publishing::new(&node).unwrap();
+        let broker = publishing.0.clone();
+        broker.release();
+        assert!(shared(&broker) && !broker.released.load(Ordering::SeqCst));
+        drop(publishing);
+        broker.release();
+        assert!(!shared(&broker) && broker.released.load(Ordering::SeqCst));
+        let publishing = Publishing::new(&node).unwrap();
+        assert!(!Arc::ptr_eq(&publishing.0, &broker));
+    }
+
+    #[tokio::test]
+    #[ignore = "需要 127.0.0.1:1883 上的 MQTT 服务器，例如 mosquitto"]
+    async fn publishes_to_subscribers() {
+        let config = json!({ "url": "mqtt://127.0.0.1:1883", "topic": "n2s/test/a", "payload": r#"{"n":1}"#, "qos": "1" });
+        let broker = broker(&node(config.clone())).unwrap();
+        let mut subscription = broker.subscribe(vec!["n2s/test/+".to_string()], QoS::AtLeastOnce).await.unwrap();
+        broker.wait_connected(Duration::from_secs(5)).await.unwrap();
+        let (_, output) = execute(&node(config), &None).await.unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&output).unwrap(),
+            json!({ "topic": "n2s/test/a", "qos": 1, "retain": false, "size": 7 })
+        );
+        let publish = tokio::time::timeout(Duration::from_secs(5), subscription.messages.recv()).await.unwrap().unwrap();
+        assert_eq!(publish.topic, "n2s/test/a");
+        assert_eq!(payload_value(&publish.payload).unwrap(), json!({ "n": 1 }));
+    }
+}
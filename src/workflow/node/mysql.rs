@@ -0,0 +1,163 @@
+use std::{convert::Infallible, time::Duration};
+
+use anyhow::{anyhow, bail};
+use axum::response::sse::Event;
+use mysql_async::{
+    Conn, OptsBuilder, QueryResult, Row, consts::ColumnType, prelude::{Protocol, Queryable}
+};
+use serde_json::{Value, json};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::{
+    super::model::{Log, Node}, sql::{self, Column, Parameter}
+};
+
+/// binary 字符集，用于区分 BLOB 与 TEXT
+const BINARY_CHARSET: u16 = 63;
+
+pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
+    let mut logs = vec![];
+
+    let host = node.config.get("host").map_or("localhost", |v| v.as_str());
+    let port = node.config.get("port").and_then(|v| v.trim().parse::<u16>().ok()).unwrap_or(3306);
+    let database = node.config.get("database").filter(|v| !v.is_empty());
+    let username = node.config.get("username").filter(|v| !v.is_empty());
+    let password = node.config.get("password").map_or("", |v| v.as_str());
+    let query = node.config.get("query").filter(|v| !v.trim().is_empty());
+
+    let (Some(database), Some(username), Some(query)) = (database, username, query) else {
+        let message = if database.is_none() {
+            "数据库名称为空"
+        } else if username.is_none() {
+            "用户名为空"
+        } else {
+            "SQL查询为空"
+        };
+        bail!(message);
+    };
+
+    sql::log(
+        node,
+        "mysql-info".to_string(),
+        format!(
+            "正在连接到 MySQL 数据库: {}@{}:{}/{}",
+            username, host, port, database
+        ),
+        &mut logs,
+        sender,
+    );
+
+    let opts = OptsBuilder::default().ip_or_hostname(host).tcp_port(port).db_name(Some(database)).user(Some(username)).pass(Some(password));
+    let mut conn = tokio::time::timeout(Duration::from_secs(30), Conn::new(opts)).await.map_err(|_| anyhow!("连接数据库超时"))?.map_err(|e| anyhow!("连接数据库失败: {}", e))?;
+
+    let query_result = tokio::time::timeout(
+        Duration::from_secs(30),
+        execute_query(&mut conn, query, sql::parameters(node)),
+    )
+    .await;
+    let output = sql::output(node, query_result, &mut logs, sender);
+    let _ = conn.disconnect().await;
+
+    Ok((logs, output?))
+}
+
+async fn execute_query(conn: &mut Conn, query: &str, params: anyhow::Result<Vec<Parameter>>) -> anyhow::Result<Value> {
+    let params = params?;
+    // 无参数时使用文本协议以支持任意语句，值以字节返回，按列类型转换；有参数时使用预处理语句
+    if params.is_empty() {
+        collect(conn.query_iter(query).await?).await
+    } else {
+        let values = params.iter().map(bind).collect::<anyhow::Result<Vec<_>>>()?;
+        collect(conn.exec_iter(query, values).await?).await
+    }
+}
+
+async fn collect<P: Protocol>(mut result: QueryResult<'_, '_, P>) -> anyhow::Result<Value> {
+    if result.columns_ref().is_empty() {
+        let affected_rows = result.affected_rows();
+        result.drop_result().await?;
+        return Ok(sql::affected_result(affected_rows));
+    }
+    let columns: Vec<Column> = result.columns_ref().iter().map(|column| Column { name: column.name_str().to_string(), kind: type_name(column.column_type()) }).collect();
+    let rows: Vec<Row> = result.collect().await?;
+
+    let data_rows = rows
+        .into_iter()
+        .map(|row| {
+            let row_columns = row.columns();
+            row.unwrap().into_iter().zip(row_columns.iter()).map(|(value, column)| convert(value, column.column_type(), column.character_set())).collect()
+        })
+        .collect();
+
+    Ok(sql::query_result(columns, data_rows))
+}
+
+/// 按声明的类型转换参数，日期等类型以字符串传入由 MySQL 转换
+fn bind(param: &Parameter) -> anyhow::Result<mysql_async::Value> {
+    use mysql_async::Value as MySqlValue;
+    let value = match param.kind.as_str() {
+        "int" | "integer" | "tinyint" | "smallint" | "bigint" | "int2" | "int4" | "int8" => param.integer()?.map(MySqlValue::Int),
+        "float" | "double" | "real" | "float4" | "float8" => param.float()?.map(MySqlValue::Double),
+        "bool" | "boolean" => param.boolean()?.map(|v| MySqlValue::Int(v as i64)),
+        "json" => param.json().map(|v| MySqlValue::Bytes(v.to_string().into_bytes())),
+        _ => param.text().map(|v| MySqlValue::Bytes(v.into_bytes())),
+    };
+    Ok(value.unwrap_or(MySqlValue::NULL))
+}
+
+/// MYSQL_TYPE_LONG -> long
+fn type_name(column_type: ColumnType) -> String {
+    format!("{:?}", column_type).trim_start_matches("MYSQL_TYPE_").to_lowercase()
+}
+
+fn convert(value: mysql_async::Value, column_type: ColumnType, charset: u16) -> Value {
+    use mysql_async::Value as MySqlValue;
+    match value {
+        MySqlValue::NULL => Value::Null,
+        MySqlValue::Int(v) => json!(v),
+        MySqlValue::UInt(v) => json!(v),
+        MySqlValue::Float(v) => json!(v),
+        MySqlValue::Double(v) => json!(v),
+        MySqlValue::Date(year, month, day, hour, minute, second, _) => match chrono::NaiveDate::from_ymd_opt(year as i32, month as u32, day as u32) {
+            Some(date) if column_type == ColumnType::MYSQL_TYPE_DATE => sql::date(date),
+            Some(date) => date.and_hms_opt(hour as u32, minute as u32, second as u32).map(sql::datetime).unwrap_or(Value::Null),
+            None => Value::Null,
+        },
+        MySqlValue::Time(negative, days, hours, minutes, seconds, _) => json!(format!(
+            "{}{:02}:{:02}:{:02}",
+            if negative { "-" } else { "" },
+            days * 24 + hours as u32,
+            minutes,
+            seconds
+        )),
+        MySqlValue::Bytes(bytes) => {
+            let text = || String::from_utf8_lossy(&bytes).to_string();
+            match column_type {
+                ColumnType::MYSQL_TYPE_TINY
+                | ColumnType::MYSQL_TYPE_SHORT
+                | ColumnType::MYSQL_TYPE_LONG
+                | ColumnType::MYSQL_TYPE_LONGLONG
+                | ColumnType::MYSQL_TYPE_INT24
+                | ColumnType::MYSQL_TYPE_YEAR => {
+                    let text = text();
+                    text.parse::<i64>().map(|v| json!(v)).or_else(|_| text.parse::<u64>().map(|v| json!(v))).unwrap_or(json!(text))
+                }
+                ColumnType::MYSQL_TYPE_FLOAT | ColumnType::MYSQL_TYPE_DOUBLE => text().parse::<f64>().map(|v| json!(v)).unwrap_or_else(|_| json!(text())),
+                ColumnType::MYSQL_TYPE_JSON => serde_json::from_slice(&bytes).unwrap_or_else(|_| json!(text())),
+                ColumnType::MYSQL_TYPE_TINY_BLOB
+                | ColumnType::MYSQL_TYPE_MEDIUM_BLOB
+                | ColumnType::MYSQL_TYPE_LONG_BLOB
+                | ColumnType::MYSQL_TYPE_BLOB
+                | ColumnType::MYSQL_TYPE_VAR_STRING
+                | ColumnType::MYSQL_TYPE_STRING
+                | ColumnType::MYSQL_TYPE_BIT
+                    if charset == BINARY_CHARSET =>
+                {
+                    sql::bytes(&bytes)
+                }
+                // DECIMAL 以字符串输出，避免精度丢失
+                _ => json!(text()),
+            }
+        }
+    }
+}
@@ -12,8 +12,8 @@ pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event,
     let output = node.config.get("output").map(|v| v.to_string());
     let log_data = LogData { kind: "output".to_string(), data: output.clone(), node_id: node.id.clone(), node_type: None, result: None };
     sse::send_json(log_data.clone(), sender)?;
-    return Ok((
+    Ok((
         vec![Log { timestamp: Utc::now(), data: log_data }],
         output.unwrap_or("".to_string()),
-    ));
+    ))
 }
@@ -1,175 +1,718 @@
-use std::{convert::Infallible, time::Duration};
+use std::{collections::HashMap, convert::Infallible, error::Error, sync::OnceLock, time::Duration};
 
+use anyhow::{anyhow, bail};
 use axum::response::sse::Event;
 use chrono::Utc;
-use serde_json::json;
-use tokio::sync::mpsc::UnboundedSender;
-use tokio_postgres::{Client, NoTls};
+use deadpool_postgres::{Client, Config, GenericClient, Pool, PoolConfig, Runtime, SslMode};
+use fallible_iterator::FallibleIterator;
+use postgres_protocol::types;
+use ring::digest;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use tokio::sync::{
+    Mutex, mpsc::{self, UnboundedSender}
+};
+use tokio_postgres::{
+    NoTls, Row, types::{Field, FromSql, Kind, ToSql, Type}
+};
+use tokio_postgres_rustls::MakeRustlsConnect;
 
-use super::super::{
-    model::{Log, LogData, Node}, sse::send_json
+use super::{
+    super::{
+        model::{Log, Node}, stream::{self, Items}, tls
+    }, sql::{self, Column, Parameter}
 };
 
+static POOLS: OnceLock<Mutex<HashMap<Vec<u8>, Pool>>> = OnceLock::new();
+
+/// 事务中的一条语句
+#[derive(Deserialize)]
+struct Statement {
+    query: String,
+    #[serde(default)]
+    params: Vec<Parameter>,
+}
+
 pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
     let mut logs = vec![];
-    let mut output = String::new();
 
     // Get configuration parameters
     let host = node.config.get("host").map_or("localhost", |v| v.as_str());
-    let port = node.config.get("port").map_or("5432", |v| v.as_str());
-    let database = node.config.get("database");
-    let username = node.config.get("username");
-    let password = node.config.get("password");
-    let query = node.config.get("query");
+    let port = node.config.get("port").and_then(|v| v.trim().parse::<u16>().ok()).unwrap_or(5432);
+    let database = node.config.get("database").filter(|v| !v.is_empty());
+    let username = node.config.get("username").filter(|v| !v.is_empty());
+    let password = node.config.get("password").map_or("", |v| v.as_str());
+    let query = node.config.get("query").filter(|v| !v.trim().is_empty());
+    // 多条语句在同一事务中执行：[{"query": "...", "params": [...]}, ...]
+    let statements = node.config.get("statements").filter(|v| !v.trim().is_empty());
+    let timeout = node.config.get("timeout").and_then(|v| v.trim().parse::<u64>().ok()).unwrap_or(30);
 
     // Validate required parameters
-    if database.is_none() {
-        let log_data = LogData { kind: "postgresql-error".to_string(), data: Some("数据库名称为空".to_string()), node_id: node.id.clone(), node_type: None, result: None };
-        logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
-        send_json(log_data, sender).unwrap();
-        return Ok((logs, output));
+    let (Some(database), Some(username), true) = (database, username, query.is_some() || statements.is_some()) else {
+        let message = if database.is_none() {
+            "数据库名称为空"
+        } else if username.is_none() {
+            "用户名为空"
+        } else {
+            "SQL查询为空"
+        };
+        bail!(message);
+    };
+
+    // Log connection attempt
+    sql::log(
+        node,
+        "postgresql-info".to_string(),
+        format!(
+            "正在连接到 PostgreSQL 数据库: {}@{}:{}/{}",
+            username, host, port, database
+        ),
+        &mut logs,
+        sender,
+    );
+
+    // 从连接池获取连接
+    let client = match pool(node, host, port, database, username, password).await {
+        Ok(pool) => tokio::time::timeout(Duration::from_secs(timeout), pool.get()).await.map_err(|_| anyhow!("获取连接超时")).and_then(|client| client.map_err(|e| anyhow!(e))),
+        Err(e) => Err(e),
+    };
+    let mut client = client.map_err(|e| anyhow!("连接数据库失败: {}", e))?;
+
+    // Execute query with timeout
+    let run = async {
+        match statements {
+            Some(statements) => {
+                let statements: Vec<Statement> = serde_json::from_str(statements).map_err(|e| anyhow!("statements 配置不是合法的 JSON 数组: {}", e))?;
+                execute_transaction(&mut client, statements).await
+            }
+            None => {
+                execute_query(
+                    &client,
+                    query.map_or("", |v| v.as_str()),
+                    sql::parameters(node)?,
+                )
+                .await
+            }
+        }
+    };
+    let query_result = tokio::time::timeout(Duration::from_secs(timeout), run).await;
+    let output = sql::output(node, query_result, &mut logs, sender)?;
+
+    Ok((logs, output))
+}
+
+/// 按连接配置复用连接池。sslMode 为 disable（默认）、prefer 或 require，sslRootCert 为额外信任的 CA 证书（PEM）
+async fn pool(node: &Node, host: &str, port: u16, database: &str, username: &str, password: &str) -> anyhow::Result<Pool> {
+    let ssl_mode = node.config.get("sslMode").map(|v| v.trim().to_lowercase()).filter(|v| !v.is_empty()).unwrap_or("disable".to_string());
+    let root_cert = node.config.get("sslRootCert").map(|v| v.trim()).filter(|v| !v.is_empty());
+    let pool_size = node.config.get("poolSize").and_then(|v| v.trim().parse::<usize>().ok()).filter(|v| *v > 0).unwrap_or(16);
+
+    // 以连接参数的摘要作为键，不在连接池表中保存明文密码
+    let key = format!(
+        "{}@{}:{}/{}?password={}&sslMode={}&sslRootCert={}&poolSize={}",
+        username,
+        host,
+        port,
+        database,
+        password,
+        ssl_mode,
+        root_cert.unwrap_or_default(),
+        pool_size
+    );
+    let key = digest::digest(&digest::SHA256, key.as_bytes()).as_ref().to_vec();
+    let mut pools = POOLS.get_or_init(Default::default).lock().await;
+    if let Some(pool) = pools.get(&key) {
+        return Ok(pool.clone());
     }
 
-    if username.is_none() {
-        let log_data = LogData { kind: "postgresql-error".to_string(), data: Some("用户名为空".to_string()), node_id: node.id.clone(), node_type: None, result: None };
-        logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
-        send_json(log_data, sender).unwrap();
-        return Ok((logs, output));
+    let mut config = Config::new();
+    config.host = Some(host.to_string());
+    config.port = Some(port);
+    config.dbname = Some(database.to_string());
+    config.user = Some(username.to_string());
+    config.password = Some(password.to_string());
+    config.connect_timeout = Some(Duration::from_secs(10));
+    config.pool = Some(PoolConfig::new(pool_size));
+    let pool = match ssl_mode.as_str() {
+        "disable" => config.create_pool(Some(Runtime::Tokio1), NoTls)?,
+        "prefer" | "require" => {
+            config.ssl_mode = Some(if ssl_mode == "require" { SslMode::Require } else { SslMode::Prefer });
+            config.create_pool(Some(Runtime::Tokio1), tls_connector(root_cert)?)?
+        }
+        other => return Err(anyhow!("不支持的 sslMode: {}", other)),
+    };
+    pools.insert(key, pool.clone());
+    Ok(pool)
+}
+
+fn tls_connector(root_cert: Option<&str>) -> anyhow::Result<MakeRustlsConnect> {
+    Ok(MakeRustlsConnect::new(tls::client_config(root_cert)?))
+}
+
+/// 在同一事务中依次执行，任一语句失败时回滚
+async fn execute_transaction(client: &mut Client, statements: Vec<Statement>) -> anyhow::Result<Value> {
+    let transaction = client.transaction().await?;
+    let mut results = Vec::new();
+    for (index, statement) in statements.into_iter().enumerate() {
+        match execute_query(&transaction, &statement.query, statement.params).await {
+            Ok(result) => results.push(result),
+            Err(e) => {
+                transaction.rollback().await?;
+                return Err(anyhow!(
+                    "第 {} 条语句执行失败，事务已回滚: {}",
+                    index + 1,
+                    e
+                ));
+            }
+        }
     }
+    transaction.commit().await?;
+    Ok(json!({
+        "success": true,
+        "message": format!("事务执行成功，共 {} 条语句", results.len()),
+        "results": results
+    }))
+}
 
-    if query.is_none() {
-        let log_data = LogData { kind: "postgresql-error".to_string(), data: Some("SQL查询为空".to_string()), node_id: node.id.clone(), node_type: None, result: None };
-        logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
-        send_json(log_data, sender).unwrap();
-        return Ok((logs, output));
+async fn execute_query(client: &impl GenericClient, query: &str, params: Vec<Parameter>) -> anyhow::Result<Value> {
+    let (types, values): (Vec<Type>, Vec<Box<dyn ToSql+Sync+Send>>) = params.iter().map(bind).collect::<anyhow::Result<Vec<_>>>()?.into_iter().unzip();
+    let statement = client.prepare_typed(query, &types).await?;
+    let values: Vec<&(dyn ToSql+Sync)> = values.iter().map(|value| value.as_ref() as &(dyn ToSql+Sync)).collect();
+    if statement.columns().is_empty() {
+        return Ok(sql::affected_result(
+            client.execute(&statement, &values).await?,
+        ));
     }
+    let columns = columns(&statement);
+    let rows = client.query(&statement, &values).await?;
+    let data_rows = rows.iter().map(row_values).collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(sql::query_result(columns, data_rows))
+}
+
+fn columns(statement: &tokio_postgres::Statement) -> Vec<Column> {
+    statement.columns().iter().map(|column| Column { name: column.name().to_string(), kind: column.type_().name().to_string() }).collect()
+}
 
-    let database = database.unwrap();
-    let username = username.unwrap();
-    let password = password.map_or("", |v| v.as_str());
-    let query = query.unwrap();
+fn row_values(row: &Row) -> anyhow::Result<Vec<Value>> {
+    (0..row.len()).map(|i| Ok(row.try_get::<_, Option<PgValue>>(i)?.map_or(Value::Null, |value| value.0))).collect()
+}
+
+/// 流式查询（stream=true）：在只读事务中通过游标每次读取 pageSize 行，每行作为一条输出
+pub async fn stream(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, Items)> {
+    let mut logs = vec![];
 
-    // Build connection string
-    let conn_str = format!(
-        "host={} port={} dbname={} user={} password={}",
-        host, port, database, username, password
+    let host = node.config.get("host").map_or("localhost", |v| v.as_str());
+    let port = node.config.get("port").and_then(|v| v.trim().parse::<u16>().ok()).unwrap_or(5432);
+    let database = node.config.get("database").filter(|v| !v.is_empty()).ok_or_else(|| anyhow!("数据库名称为空"))?;
+    let username = node.config.get("username").filter(|v| !v.is_empty()).ok_or_else(|| anyhow!("用户名为空"))?;
+    let password = node.config.get("password").map_or("", |v| v.as_str());
+    let query = node.config.get("query").filter(|v| !v.trim().is_empty()).cloned().ok_or_else(|| anyhow!("SQL查询为空"))?;
+    let timeout = Duration::from_secs(node.config.get("timeout").and_then(|v| v.trim().parse::<u64>().ok()).unwrap_or(30));
+    let page_size = node.config.get("pageSize").and_then(|v| v.trim().parse::<i32>().ok()).filter(|v| *v > 0).unwrap_or(500);
+    let params = sql::parameters(node)?;
+
+    sql::log(
+        node,
+        "postgresql-info".to_string(),
+        format!(
+            "正在连接到 PostgreSQL 数据库: {}@{}:{}/{}，每次读取 {} 行",
+            username, host, port, database, page_size
+        ),
+        &mut logs,
+        sender,
     );
 
-    // Log connection attempt
-    let log_data = LogData {
-        kind: "postgresql-info".to_string(),
-        data: Some(format!(
-            "正在连接到 PostgreSQL 数据库: {}@{}:{}/{}",
-            username, host, port, database
-        )),
-        node_id: node.id.clone(),
-        node_type: None,
-        result: None,
-    };
-    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
-    send_json(log_data, sender).unwrap();
-
-    // Connect to database
-    let (client, connection) = match tokio_postgres::connect(&conn_str, NoTls).await {
-        Ok((client, connection)) => (client, connection),
-        Err(e) => {
-            let log_data = LogData { kind: "postgresql-error".to_string(), data: Some(format!("连接数据库失败: {}", e)), node_id: node.id.clone(), node_type: None, result: None };
-            logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
-            send_json(log_data, sender).unwrap();
-            return Ok((logs, output));
+    let pool = pool(node, host, port, database, username, password).await?;
+    let (tx, items) = stream::channel();
+    tokio::spawn(async move {
+        if let Err(e) = fetch(pool, &query, params, page_size, timeout, &tx).await {
+            let _ = tx.send(Err(anyhow!("查询执行失败: {}", e))).await;
         }
-    };
+    });
+    Ok((logs, items))
+}
 
-    // Spawn connection task
-    let _handle = tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprintln!("PostgreSQL connection error: {}", e);
+async fn fetch(pool: Pool, query: &str, params: Vec<Parameter>, page_size: i32, timeout: Duration, tx: &mpsc::Sender<anyhow::Result<String>>) -> anyhow::Result<()> {
+    let mut client = tokio::time::timeout(timeout, pool.get()).await.map_err(|_| anyhow!("获取连接超时"))??;
+    // 游标只在事务内有效
+    let transaction = client.build_transaction().read_only(true).start().await?;
+    let (types, values): (Vec<Type>, Vec<Box<dyn ToSql+Sync+Send>>) = params.iter().map(bind).collect::<anyhow::Result<Vec<_>>>()?.into_iter().unzip();
+    let statement = transaction.prepare_typed(query, &types).await?;
+    let columns = columns(&statement);
+    let values: Vec<&(dyn ToSql+Sync)> = values.iter().map(|value| value.as_ref() as &(dyn ToSql+Sync)).collect();
+    let portal = transaction.bind(&statement, &values).await?;
+    loop {
+        let rows = tokio::time::timeout(timeout, transaction.query_portal(&portal, page_size)).await.map_err(|_| anyhow!("查询超时"))??;
+        for row in &rows {
+            let item = sql::row_object(&columns, row_values(row)?).to_string();
+            // 下游已停止接收
+            if tx.send(Ok(item)).await.is_err() {
+                return Ok(());
+            }
         }
-    });
+        if rows.len() < page_size as usize {
+            break;
+        }
+    }
+    transaction.commit().await?;
+    Ok(())
+}
 
-    // Execute query with timeout
-    let query_result = tokio::time::timeout(Duration::from_secs(30), execute_query(&client, query)).await;
+/// 按声明的类型转换参数，类型必须与 SQL 中参数的类型一致
+fn bind(param: &Parameter) -> anyhow::Result<(Type, Box<dyn ToSql+Sync+Send>)> {
+    let invalid = |kind: &str| anyhow!("参数 {} 不是合法的 {}", param.value, kind);
+    Ok(match param.kind.as_str() {
+        "text" | "varchar" => (Type::TEXT, Box::new(param.text())),
+        "int2" | "smallint" => (
+            Type::INT2,
+            Box::new(param.integer()?.map(i16::try_from).transpose()?),
+        ),
+        "int4" | "int" | "integer" => (
+            Type::INT4,
+            Box::new(param.integer()?.map(i32::try_from).transpose()?),
+        ),
+        "int8" | "bigint" => (Type::INT8, Box::new(param.integer()?)),
+        "float4" | "real" => (Type::FLOAT4, Box::new(param.float()?.map(|v| v as f32))),
+        "float8" | "double precision" => (Type::FLOAT8, Box::new(param.float()?)),
+        "bool" | "boolean" => (Type::BOOL, Box::new(param.boolean()?)),
+        "json" => (Type::JSON, Box::new(param.json())),
+        "jsonb" => (Type::JSONB, Box::new(param.json())),
+        "uuid" => (
+            Type::UUID,
+            Box::new(param.text().filter(|v| !v.trim().is_empty()).map(|v| uuid::Uuid::parse_str(v.trim()).map_err(|_| invalid("uuid"))).transpose()?),
+        ),
+        "date" => (
+            Type::DATE,
+            Box::new(param.text().filter(|v| !v.trim().is_empty()).map(|v| chrono::NaiveDate::parse_from_str(v.trim(), "%Y-%m-%d").map_err(|_| invalid("date"))).transpose()?),
+        ),
+        "timestamp" => (
+            Type::TIMESTAMP,
+            Box::new(
+                param
+                    .text()
+                    .filter(|v| !v.trim().is_empty())
+                    .map(|v| chrono::NaiveDateTime::parse_from_str(v.trim().replace('T', " ").as_str(), "%Y-%m-%d %H:%M:%S%.f").map_err(|_| invalid("timestamp")))
+                    .transpose()?,
+            ),
+        ),
+        "timestamptz" => (
+            Type::TIMESTAMPTZ,
+            Box::new(
+                param.text().filter(|v| !v.trim().is_empty()).map(|v| chrono::DateTime::parse_from_rfc3339(v.trim()).map(|v| v.with_timezone(&Utc)).map_err(|_| invalid("timestamptz"))).transpose()?,
+            ),
+        ),
+        // numeric 等其他类型以文本传入，在 SQL 中显式转换，例如 $1::numeric
+        other => {
+            return Err(anyhow!(
+                "不支持的参数类型 {}，请使用 text 并在 SQL 中转换类型",
+                other
+            ));
+        }
+    })
+}
+
+type FromSqlError = Box<dyn Error+Sync+Send>;
+
+/// 复合值中的字段：类型 oid 与数据，NULL 为 None
+type RecordField<'a> = (u32, Option<&'a [u8]>);
 
-    match query_result {
-        Ok(Ok(result)) => {
-            output = result.clone();
-            let log_data = LogData { kind: "output".to_string(), data: Some(result.clone()), node_id: node.id.clone(), node_type: None, result: Some(result) };
-            logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
-            send_json(log_data, sender).unwrap();
+/// 按列类型把任意 PostgreSQL 值转换为 JSON
+struct PgValue(Value);
+
+impl<'a> FromSql<'a> for PgValue {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, FromSqlError> {
+        Ok(PgValue(convert(ty, raw)?))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+/// numeric 输出为精确的字符串，timestamptz 输出为 RFC 3339，bytea 输出为 base64，数组与复合类型递归转换。
+/// money、timetz、macaddr、point、bit、tsvector、range 等没有转换的类型，二进制数据原样以 base64 输出
+fn convert(ty: &Type, raw: &[u8]) -> Result<Value, FromSqlError> {
+    Ok(match *ty {
+        Type::BOOL => json!(bool::from_sql(ty, raw)?),
+        Type::CHAR => json!(i8::from_sql(ty, raw)?),
+        Type::INT2 => json!(i16::from_sql(ty, raw)?),
+        Type::INT4 => json!(i32::from_sql(ty, raw)?),
+        Type::INT8 => json!(i64::from_sql(ty, raw)?),
+        Type::OID => json!(u32::from_sql(ty, raw)?),
+        Type::FLOAT4 => json!(f32::from_sql(ty, raw)?),
+        Type::FLOAT8 => json!(f64::from_sql(ty, raw)?),
+        Type::NUMERIC => json!(numeric(raw)?),
+        Type::JSON | Type::JSONB => Value::from_sql(ty, raw)?,
+        Type::UUID => json!(uuid::Uuid::from_sql(ty, raw)?.to_string()),
+        Type::TIMESTAMP | Type::TIMESTAMPTZ if raw == i64::MAX.to_be_bytes() => json!("infinity"),
+        Type::TIMESTAMP | Type::TIMESTAMPTZ if raw == i64::MIN.to_be_bytes() => json!("-infinity"),
+        Type::TIMESTAMP => sql::datetime(chrono::NaiveDateTime::from_sql(ty, raw)?),
+        Type::TIMESTAMPTZ => json!(chrono::DateTime::<Utc>::from_sql(ty, raw)?.to_rfc3339()),
+        Type::DATE if raw == i32::MAX.to_be_bytes() => json!("infinity"),
+        Type::DATE if raw == i32::MIN.to_be_bytes() => json!("-infinity"),
+        Type::DATE => sql::date(chrono::NaiveDate::from_sql(ty, raw)?),
+        Type::TIME => json!(chrono::NaiveTime::from_sql(ty, raw)?.to_string()),
+        Type::INTERVAL => json!(interval(raw)?),
+        Type::BYTEA => sql::bytes(raw),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN | Type::XML => text(raw)?,
+        Type::RECORD => record(raw)?,
+        Type::INET | Type::CIDR => {
+            let inet = types::inet_from_sql(raw)?;
+            let full = if inet.addr().is_ipv4() { 32 } else { 128 };
+            if *ty == Type::INET && inet.netmask() == full { json!(inet.addr().to_string()) } else { json!(format!("{}/{}", inet.addr(), inet.netmask())) }
         }
-        Ok(Err(e)) => {
-            let log_data = LogData { kind: "postgresql-error".to_string(), data: Some(format!("查询执行失败: {}", e)), node_id: node.id.clone(), node_type: None, result: None };
-            logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
-            send_json(log_data, sender).unwrap();
+        _ => match ty.kind() {
+            Kind::Array(inner) => array(inner, raw)?,
+            Kind::Domain(inner) => convert(inner, raw)?,
+            Kind::Composite(fields) => composite(fields, raw)?,
+            // 枚举与 citext 的二进制格式即文本
+            Kind::Enum(_) => text(raw)?,
+            _ if ty.name() == "citext" => text(raw)?,
+            _ => sql::bytes(raw),
+        },
+    })
+}
+
+fn read_i32(buf: &mut &[u8]) -> Result<i32, FromSqlError> {
+    let (bytes, rest) = buf.split_first_chunk::<4>().ok_or("数据长度不足")?;
+    *buf = rest;
+    Ok(i32::from_be_bytes(*bytes))
+}
+
+/// 多维数组按维度嵌套
+fn array(inner: &Type, raw: &[u8]) -> Result<Value, FromSqlError> {
+    let array = types::array_from_sql(raw)?;
+    let dimensions: Vec<usize> = array.dimensions().map(|dimension| Ok(dimension.len.max(0) as usize)).collect()?;
+    let values: Vec<Value> = array.values().map(|value| value.map_or(Ok(Value::Null), |raw| convert(inner, raw))).collect()?;
+    Ok(nest(&dimensions, values))
+}
+
+fn nest(dimensions: &[usize], values: Vec<Value>) -> Value {
+    match dimensions {
+        [_, rest @ ..] if !rest.is_empty() => {
+            let size = rest.iter().product::<usize>().max(1);
+            Value::Array(values.chunks(size).map(|chunk| nest(rest, chunk.to_vec())).collect())
         }
-        Err(_) => {
-            let log_data = LogData { kind: "postgresql-error".to_string(), data: Some("查询超时".to_string()), node_id: node.id.clone(), node_type: None, result: None };
-            logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
-            send_json(log_data, sender).unwrap();
+        _ => Value::Array(values),
+    }
+}
+
+/// 复合类型按字段名转换为对象
+fn composite(fields: &[Field], raw: &[u8]) -> Result<Value, FromSqlError> {
+    let mut object = Map::new();
+    for (field, (_, value)) in fields.iter().zip(record_fields(raw)?) {
+        object.insert(
+            field.name().to_string(),
+            value.map_or(Ok(Value::Null), |raw| convert(field.type_(), raw))?,
+        );
+    }
+    Ok(Value::Object(object))
+}
+
+/// 匿名记录（如 row(1, 'x')）没有字段名，按内置类型 oid 转换为数组
+fn record(raw: &[u8]) -> Result<Value, FromSqlError> {
+    let values = record_fields(raw)?
+        .into_iter()
+        .map(|(oid, value)| match (Type::from_oid(oid), value) {
+            (_, None) => Ok(Value::Null),
+            (Some(ty), Some(raw)) => convert(&ty, raw),
+            (None, Some(raw)) => Ok(sql::bytes(raw)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Value::Array(values))
+}
+
+/// 复合值二进制格式：字段数，每个字段为 oid、长度（-1 为 NULL）与数据
+fn record_fields(mut raw: &[u8]) -> Result<Vec<RecordField<'_>>, FromSqlError> {
+    let count = read_i32(&mut raw)?.max(0) as usize;
+    let mut fields = Vec::with_capacity(count);
+    for _ in 0..count {
+        let oid = read_i32(&mut raw)? as u32;
+        let len = read_i32(&mut raw)?;
+        if len < 0 {
+            fields.push((oid, None));
+        } else {
+            let (bytes, rest) = raw.split_at_checked(len as usize).ok_or("数据长度不足")?;
+            raw = rest;
+            fields.push((oid, Some(bytes)));
         }
     }
+    Ok(fields)
+}
+
+/// 文本类型的值输出为字符串
+fn text(raw: &[u8]) -> Result<Value, FromSqlError> {
+    Ok(json!(std::str::from_utf8(raw)?))
+}
+
+/// numeric 二进制格式：位数、权重、符号、小数位数以及 base 10000 的各位数字
+fn numeric(raw: &[u8]) -> Result<String, FromSqlError> {
+    let read = |index: usize| raw.get(index * 2..index * 2 + 2).map(|b| u16::from_be_bytes([b[0], b[1]])).ok_or("numeric 数据长度不足");
+    let ndigits = read(0)? as usize;
+    let weight = read(1)? as i16 as i32;
+    let sign = read(2)?;
+    let dscale = read(3)? as usize;
+    match sign {
+        0xC000 => return Ok("NaN".to_string()),
+        0xD000 => return Ok("Infinity".to_string()),
+        0xF000 => return Ok("-Infinity".to_string()),
+        _ => {}
+    }
+    let digits = (0..ndigits).map(|i| read(4 + i)).collect::<Result<Vec<_>, _>>()?;
+    let digit = |position: i32| usize::try_from(position).ok().and_then(|i| digits.get(i)).copied().unwrap_or(0);
 
-    Ok((logs, output))
+    let mut result = String::new();
+    if sign == 0x4000 {
+        result.push('-');
+    }
+    if weight < 0 {
+        result.push('0');
+    } else {
+        result.push_str(&digit(0).to_string());
+        for position in 1..=weight {
+            result.push_str(&format!("{:04}", digit(position)));
+        }
+    }
+    if dscale > 0 {
+        let mut fraction = String::new();
+        let mut position = weight + 1;
+        while fraction.len() < dscale {
+            fraction.push_str(&format!("{:04}", digit(position)));
+            position += 1;
+        }
+        fraction.truncate(dscale);
+        result.push('.');
+        result.push_str(&fraction);
+    }
+    Ok(result)
 }
 
-async fn execute_query(client: &Client, query: &str) -> anyhow::Result<String> {
-    let rows = client.query(query, &[]).await?;
+/// interval 输出为 ISO 8601 时长，例如 P1M2DT3600S
+fn interval(mut raw: &[u8]) -> Result<String, FromSqlError> {
+    let (micros, rest) = raw.split_first_chunk::<8>().ok_or("interval 数据长度不足")?;
+    let micros = i64::from_be_bytes(*micros);
+    raw = rest;
+    let days = read_i32(&mut raw)?;
+    let months = read_i32(&mut raw)?;
+    let seconds = micros as f64 / 1_000_000.0;
+    Ok(format!("P{}M{}DT{}S", months, days, seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// numeric 二进制格式：位数、权重、符号、小数位数以及 base 10000 的各位数字
+    fn numeric_bytes(weight: i16, sign: u16, dscale: u16, digits: &[u16]) -> Vec<u8> {
+        let mut raw = Vec::new();
+        for value in [digits.len() as u16, weight as u16, sign, dscale].iter().chain(digits) {
+            raw.extend_from_slice(&value.to_be_bytes());
+        }
+        raw
+    }
+
+    fn interval_bytes(micros: i64, days: i32, months: i32) -> Vec<u8> {
+        [&micros.to_be_bytes()[..], &days.to_be_bytes(), &months.to_be_bytes()].concat()
+    }
 
-    if rows.is_empty() {
-        let result = json!({
-            "success": true,
-            "message": "查询执行成功，无结果返回",
-            "columns": [],
-            "data": [],
-            "row_count": 0
-        });
-        return Ok(result.to_string());
+    /// 数组二进制格式：维数、是否有 NULL、元素类型 oid，每维的长度与下界，然后是各元素的长度（-1 为 NULL）与数据
+    fn array_bytes(element: &Type, dimensions: &[i32], values: &[Option<Vec<u8>>]) -> Vec<u8> {
+        let mut raw = Vec::new();
+        raw.extend_from_slice(&(dimensions.len() as i32).to_be_bytes());
+        raw.extend_from_slice(&(values.iter().any(Option::is_none) as i32).to_be_bytes());
+        raw.extend_from_slice(&element.oid().to_be_bytes());
+        for len in dimensions {
+            raw.extend_from_slice(&len.to_be_bytes());
+            raw.extend_from_slice(&1i32.to_be_bytes());
+        }
+        raw.extend_from_slice(&fields_bytes(
+            values.iter().map(|value| (None, value.as_deref())),
+        ));
+        raw
     }
 
-    // Get column names and types
-    let columns = rows[0].columns();
+    fn record_bytes(fields: &[(&Type, Option<Vec<u8>>)]) -> Vec<u8> {
+        let mut raw = (fields.len() as i32).to_be_bytes().to_vec();
+        raw.extend_from_slice(&fields_bytes(
+            fields.iter().map(|(ty, value)| (Some(ty.oid()), value.as_deref())),
+        ));
+        raw
+    }
 
-    // Extract data rows
-    let mut data_rows = Vec::new();
-    for row in &rows {
-        let mut row_object = serde_json::Map::new();
+    fn fields_bytes<'a>(values: impl Iterator<Item=(Option<u32>, Option<&'a [u8]>)>) -> Vec<u8> {
+        let mut raw = Vec::new();
+        for (oid, value) in values {
+            if let Some(oid) = oid {
+                raw.extend_from_slice(&oid.to_be_bytes());
+            }
+            match value {
+                Some(value) => {
+                    raw.extend_from_slice(&(value.len() as i32).to_be_bytes());
+                    raw.extend_from_slice(value);
+                }
+                None => raw.extend_from_slice(&(-1i32).to_be_bytes()),
+            }
+        }
+        raw
+    }
 
-        for (i, column) in columns.iter().enumerate() {
-            let column_name = column.name();
+    fn int4(value: i32) -> Option<Vec<u8>> {
+        Some(value.to_be_bytes().to_vec())
+    }
 
-            // Try to get value as different types
-            let value = if let Ok(val) = row.try_get::<_, String>(i) {
-                json!(val)
-            } else if let Ok(val) = row.try_get::<_, i32>(i) {
-                json!(val)
-            } else if let Ok(val) = row.try_get::<_, i64>(i) {
-                json!(val)
-            } else if let Ok(val) = row.try_get::<_, f32>(i) {
-                json!(val)
-            } else if let Ok(val) = row.try_get::<_, f64>(i) {
-                json!(val)
-            } else if let Ok(val) = row.try_get::<_, bool>(i) {
-                json!(val)
-            } else if let Ok(val) = row.try_get::<_, chrono::NaiveDateTime>(i) {
-                json!(val.format("%Y-%m-%d %H:%M:%S").to_string())
-            } else if let Ok(val) = row.try_get::<_, chrono::NaiveDate>(i) {
-                json!(val.format("%Y-%m-%d").to_string())
-            } else {
-                json!(null)
-            };
+    #[tokio::test]
+    async fn fails_on_invalid_config_and_connection_errors() {
+        let node = |config: Value| -> Node { serde_json::from_value(json!({ "id": "pg", "type": "postgresql", "position": { "x": 0, "y": 0 }, "config": config })).unwrap() };
+        let error = execute(
+            &node(json!({ "username": "u", "query": "select 1" })),
+            &None,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.to_string(), "数据库名称为空");
+        let error = execute(&node(json!({ "database": "d", "username": "u" })), &None).await.unwrap_err();
+        assert_eq!(error.to_string(), "SQL查询为空");
+        let config = json!({ "host": "127.0.0.1", "port": "1", "database": "d", "username": "u", "query": "select 1", "timeout": "5" });
+        let error = execute(&node(config), &None).await.unwrap_err();
+        assert!(
+            error.to_string().starts_with("连接数据库失败: "),
+            "{}",
+            error
+        );
+    }
 
-            row_object.insert(column_name.to_string(), value);
+    #[test]
+    fn decodes_numeric() {
+        let cases: [(i16, u16, u16, &[u16], &str); 9] = [
+            (0, 0x0000, 2, &[123, 4500], "123.45"),
+            (0, 0x4000, 2, &[123, 4500], "-123.45"),
+            (-1, 0x0000, 4, &[12], "0.0012"),
+            (-2, 0x4000, 6, &[100], "-0.000001"),
+            (1, 0x0000, 1, &[1234, 5678, 9000], "12345678.9"),
+            (1, 0x0000, 0, &[100], "1000000"),
+            (2, 0x0000, 0, &[1], "100000000"),
+            (0, 0x0000, 2, &[], "0.00"),
+            (
+                0,
+                0x0000,
+                20,
+                &[3, 1415, 9265, 3589, 7932, 3846],
+                "3.14159265358979323846",
+            ),
+        ];
+        for (weight, sign, dscale, digits, expected) in cases {
+            assert_eq!(
+                convert(&Type::NUMERIC, &numeric_bytes(weight, sign, dscale, digits)).unwrap(),
+                json!(expected)
+            );
         }
+        assert_eq!(numeric(&numeric_bytes(0, 0xC000, 0, &[])).unwrap(), "NaN");
+        assert_eq!(
+            numeric(&numeric_bytes(0, 0xD000, 0, &[])).unwrap(),
+            "Infinity"
+        );
+        assert_eq!(
+            numeric(&numeric_bytes(0, 0xF000, 0, &[])).unwrap(),
+            "-Infinity"
+        );
+        assert!(numeric(&numeric_bytes(0, 0, 0, &[1, 2])[..10]).is_err());
+    }
 
-        data_rows.push(json!(row_object));
+    #[test]
+    fn decodes_interval() {
+        assert_eq!(
+            convert(&Type::INTERVAL, &interval_bytes(3_723_500_000, 2, 14)).unwrap(),
+            json!("P14M2DT3723.5S")
+        );
+        assert_eq!(
+            convert(&Type::INTERVAL, &interval_bytes(-1_000_000, -1, 0)).unwrap(),
+            json!("P0M-1DT-1S")
+        );
+        assert!(convert(&Type::INTERVAL, &interval_bytes(0, 0, 0)[..12]).is_err());
     }
 
-    let result = json!({
-        "success": true,
-        "message": format!("查询执行成功，返回 {} 条记录", data_rows.len()),
-        "data": data_rows,
-        "row_count": data_rows.len()
-    });
+    #[test]
+    fn decodes_arrays_with_nulls_and_dimensions() {
+        let raw = array_bytes(&Type::INT4, &[2, 2], &[int4(1), None, int4(3), int4(4)]);
+        assert_eq!(
+            convert(&Type::INT4_ARRAY, &raw).unwrap(),
+            json!([[1, null], [3, 4]])
+        );
+
+        let raw = array_bytes(&Type::INT4, &[2, 1, 2], &[
+            int4(1),
+            int4(2),
+            int4(3),
+            int4(4),
+        ]);
+        assert_eq!(
+            convert(&Type::INT4_ARRAY, &raw).unwrap(),
+            json!([[[1, 2]], [[3, 4]]])
+        );
+
+        let raw = array_bytes(&Type::NUMERIC, &[2], &[
+            Some(numeric_bytes(0, 0x4000, 1, &[1, 5000])),
+            None,
+        ]);
+        assert_eq!(
+            convert(&Type::NUMERIC_ARRAY, &raw).unwrap(),
+            json!(["-1.5", null])
+        );
 
-    Ok(result.to_string())
+        assert_eq!(
+            convert(&Type::TEXT_ARRAY, &array_bytes(&Type::TEXT, &[], &[])).unwrap(),
+            json!([])
+        );
+    }
+
+    #[test]
+    fn decodes_records_and_composites() {
+        let inner = record_bytes(&[(&Type::BOOL, Some(vec![1]))]);
+        let raw = record_bytes(&[(&Type::INT4, int4(1)), (&Type::TEXT, Some(b"x".to_vec())), (&Type::INT8, None), (&Type::RECORD, Some(inner))]);
+        assert_eq!(
+            convert(&Type::RECORD, &raw).unwrap(),
+            json!([1, "x", null, [true]])
+        );
+
+        let fields = vec![Field::new("id".to_string(), Type::INT4), Field::new("tags".to_string(), Type::TEXT_ARRAY)];
+        let ty = Type::new(
+            "item".to_string(),
+            90001,
+            Kind::Composite(fields),
+            "public".to_string(),
+        );
+        let tags = array_bytes(&Type::TEXT, &[2], &[Some(b"a".to_vec()), None]);
+        let raw = record_bytes(&[(&Type::INT4, int4(7)), (&Type::TEXT_ARRAY, Some(tags))]);
+        assert_eq!(
+            convert(&ty, &raw).unwrap(),
+            json!({ "id": 7, "tags": ["a", null] })
+        );
+
+        assert!(
+            convert(
+                &Type::RECORD,
+                &record_bytes(&[(&Type::TEXT, Some(b"abc".to_vec()))])[..10]
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn decodes_domains_and_enums() {
+        let domain = Type::new(
+            "posint".to_string(),
+            90002,
+            Kind::Domain(Type::INT4),
+            "public".to_string(),
+        );
+        assert_eq!(convert(&domain, &5i32.to_be_bytes()).unwrap(), json!(5));
+        let mood = Type::new(
+            "mood".to_string(),
+            90003,
+            Kind::Enum(vec!["happy".to_string()]),
+            "public".to_string(),
+        );
+        assert_eq!(convert(&mood, b"happy").unwrap(), json!("happy"));
+    }
 }
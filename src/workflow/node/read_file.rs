@@ -1,67 +1,78 @@
 use std::{convert::Infallible, fs};
 
+use anyhow::anyhow;
 use axum::response::sse::Event;
 use chrono::Utc;
-use tokio::sync::mpsc::UnboundedSender;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader}, sync::mpsc::UnboundedSender
+};
 
 use super::super::{
-    model::{Log, LogData, Node},
-    sse::send_json,
+    binary::Binary, model::{Log, LogData, Node}, sse::send_json, stream::{self, Items}
 };
 
-pub async fn execute(
-    node: &Node,
-    sender: &Option<UnboundedSender<Result<Event, Infallible>>>,
-) -> anyhow::Result<(Vec<Log>, String)> {
+pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
     let mut logs = vec![];
     let mut output = String::new();
     let path = node.config.get("path");
-    if path.is_none() {
-        let log_data = LogData {
-            kind: "read-file-error".to_string(),
-            data: Some("path 为空".to_string()),
-            node_id: node.id.clone(),
-            node_type: None,
-            result: None,
-        };
-        logs.push(Log {
-            timestamp: Utc::now(),
-            data: log_data.clone(),
-        });
-        send_json(log_data, sender).unwrap();
-    } else {
-        let path = path.unwrap();
-        match fs::read_to_string(path) {
+    match path {
+        Some(path) => match read(
+            path,
+            node.config.get("binary").is_some_and(|v| v.trim() == "true"),
+        ) {
             Ok(content) => {
                 output.push_str(&content);
-                let log_data = LogData {
-                    kind: "output".to_string(),
-                    data: Some(content.clone()),
-                    node_id: node.id.clone(),
-                    node_type: None,
-                    result: Some(content.clone()),
-                };
-                logs.push(Log {
-                    timestamp: Utc::now(),
-                    data: log_data.clone(),
-                });
+                let log_data = LogData { kind: "output".to_string(), data: Some(content.clone()), node_id: node.id.clone(), node_type: None, result: Some(content.clone()) };
+                logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
                 send_json(log_data, sender).unwrap();
             }
             Err(e) => {
-                let log_data = LogData {
-                    kind: "output".to_string(),
-                    data: Some(format!("error: {}", e)),
-                    node_id: node.id.clone(),
-                    node_type: None,
-                    result: None,
-                };
-                logs.push(Log {
-                    timestamp: Utc::now(),
-                    data: log_data.clone(),
-                });
+                let log_data = LogData { kind: "output".to_string(), data: Some(format!("error: {}", e)), node_id: node.id.clone(), node_type: None, result: None };
+                logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
                 send_json(log_data, sender).unwrap();
             }
+        },
+        _ => {
+            let log_data = LogData { kind: "read-file-error".to_string(), data: Some("path 为空".to_string()), node_id: node.id.clone(), node_type: None, result: None };
+            logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+            send_json(log_data, sender).unwrap();
         }
     }
     Ok((logs, output))
 }
+
+/// 文本文件输出内容；非 UTF-8 文件或设置 binary=true 时保存为二进制数据，输出其引用
+fn read(path: &str, binary: bool) -> anyhow::Result<String> {
+    let content = fs::read(path)?;
+    if !binary && let Ok(text) = std::str::from_utf8(&content) {
+        return Ok(text.to_string());
+    }
+    Ok(Binary::save(&content, Some(path), None)?.to_output())
+}
+
+/// 流式读取（stream=true）：逐行输出，不包含换行符
+pub async fn stream(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, Items)> {
+    let path = node.config.get("path").map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).ok_or_else(|| anyhow!("path 为空"))?;
+    let file = tokio::fs::File::open(&path).await.map_err(|e| anyhow!("打开文件 {} 失败: {}", path, e))?;
+
+    let log_data = LogData { kind: "read-file-info".to_string(), data: Some(format!("逐行读取文件: {}", path)), node_id: node.id.clone(), node_type: None, result: None };
+    send_json(log_data.clone(), sender)?;
+
+    let (tx, items) = stream::channel();
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(file).lines();
+        loop {
+            let item = match lines.next_line().await {
+                Ok(Some(line)) => Ok(line),
+                Ok(None) => break,
+                Err(e) => Err(anyhow!("读取文件 {} 失败: {}", path, e)),
+            };
+            let failed = item.is_err();
+            // 下游已停止接收或读取出错时结束
+            if tx.send(item).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+    Ok((vec![Log { timestamp: Utc::now(), data: log_data }], items))
+}
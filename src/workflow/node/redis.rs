@@ -0,0 +1,116 @@
+use std::{collections::HashMap, convert::Infallible, sync::OnceLock, time::Duration};
+
+use anyhow::{anyhow, bail};
+use axum::response::sse::Event;
+use chrono::Utc;
+use redis::{AsyncCommands, aio::ConnectionManager};
+use serde_json::{Value, json};
+use tokio::sync::{Mutex, mpsc::UnboundedSender};
+
+use super::super::{
+    model::{Log, LogData, Node}, sse
+};
+
+static CONNECTIONS: OnceLock<Mutex<HashMap<String, ConnectionManager>>> = OnceLock::new();
+static DEFAULT_URL: &str = "redis://127.0.0.1:6379";
+
+/// 按 URL 在各次执行间共享连接。ConnectionManager 是可克隆的多路复用连接，断开后自动重连
+async fn connection(url: &str) -> anyhow::Result<ConnectionManager> {
+    let mut connections = CONNECTIONS.get_or_init(Default::default).lock().await;
+    if let Some(connection) = connections.get(url) {
+        return Ok(connection.clone());
+    }
+    let client = redis::Client::open(url).map_err(|e| anyhow!("Redis 地址无效: {}", e))?;
+    let connection = ConnectionManager::new(client).await.map_err(|e| anyhow!("连接 Redis 失败: {}", e))?;
+    connections.insert(url.to_string(), connection.clone());
+    Ok(connection)
+}
+
+/// 执行单条 Redis 命令。字符串结果原样输出，空值输出空字符串，其余结果输出 JSON
+pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
+    let mut logs = vec![];
+    let url = node.config.get("url").map(|v| v.trim()).filter(|v| !v.is_empty()).unwrap_or(DEFAULT_URL);
+    let operation = node.config.get("operation").map(|v| v.trim().to_lowercase()).unwrap_or_default();
+    let key = node.config.get("key").map(|v| v.trim()).unwrap_or_default();
+    let field = node.config.get("field").map(|v| v.trim()).unwrap_or_default();
+    let value = node.config.get("value").map(|v| v.as_str()).unwrap_or_default();
+    // set 的过期时间；incr 时仅在计数器新建时设置，用于固定窗口限流
+    let ttl = node.config.get("ttl").and_then(|v| v.trim().parse::<u64>().ok()).filter(|v| *v > 0);
+    let amount = node.config.get("amount").and_then(|v| v.trim().parse::<i64>().ok()).unwrap_or(1);
+    let start = node.config.get("start").and_then(|v| v.trim().parse::<isize>().ok()).unwrap_or(0);
+    let stop = node.config.get("stop").and_then(|v| v.trim().parse::<isize>().ok()).unwrap_or(-1);
+    let channel = node.config.get("channel").map(|v| v.trim()).filter(|v| !v.is_empty()).unwrap_or(key);
+    let timeout = node.config.get("timeout").and_then(|v| v.trim().parse::<u64>().ok()).unwrap_or(30);
+
+    if operation == "publish" {
+        if channel.is_empty() {
+            bail!("channel 为空");
+        }
+    } else if key.is_empty() {
+        bail!("key 为空");
+    }
+
+    let log_data = LogData {
+        kind: "redis-info".to_string(),
+        data: Some(format!(
+            "执行 Redis 命令 {} {}",
+            operation.to_uppercase(),
+            if operation == "publish" { channel } else { key }
+        )),
+        node_id: node.id.clone(),
+        node_type: None,
+        result: None,
+    };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    sse::send_json(log_data, sender)?;
+
+    let mut con = connection(url).await?;
+    let command = async {
+        let result = match operation.as_str() {
+            "get" => json!(con.get::<_, Option<String>>(key).await?),
+            "set" => {
+                match ttl {
+                    Some(ttl) => con.set_ex::<_, _, ()>(key, value, ttl).await?,
+                    None => con.set::<_, _, ()>(key, value).await?,
+                }
+                json!("OK")
+            }
+            "del" => json!(con.del::<_, i64>(key).await?),
+            "incr" => {
+                let count = con.incr::<_, _, i64>(key, amount).await?;
+                if let Some(ttl) = ttl
+                    && count == amount
+                {
+                    con.expire::<_, ()>(key, ttl as i64).await?;
+                }
+                json!(count)
+            }
+            "expire" => json!(con.expire::<_, bool>(key, ttl.ok_or_else(|| anyhow!("ttl 为空"))? as i64).await?),
+            "hget" => json!(con.hget::<_, _, Option<String>>(key, field).await?),
+            "hset" => json!(con.hset::<_, _, _, i64>(key, field, value).await?),
+            "hgetall" => json!(con.hgetall::<_, HashMap<String, String>>(key).await?),
+            "hdel" => json!(con.hdel::<_, _, i64>(key, field).await?),
+            "lpush" => json!(con.lpush::<_, _, i64>(key, value).await?),
+            "rpush" => json!(con.rpush::<_, _, i64>(key, value).await?),
+            "lpop" => json!(con.lpop::<_, Option<String>>(key, None).await?),
+            "rpop" => json!(con.rpop::<_, Option<String>>(key, None).await?),
+            "lrange" => json!(con.lrange::<_, Vec<String>>(key, start, stop).await?),
+            "llen" => json!(con.llen::<_, i64>(key).await?),
+            "publish" => json!(con.publish::<_, _, i64>(channel, value).await?),
+            _ => bail!("不支持的 Redis 操作: {}", operation),
+        };
+        anyhow::Ok(result)
+    };
+    let result = tokio::time::timeout(Duration::from_secs(timeout), command).await.map_err(|_| anyhow!("Redis 命令超时"))??;
+
+    let output = match result {
+        Value::String(s) => s,
+        Value::Null => String::new(),
+        other => other.to_string(),
+    };
+    let log_data = LogData { kind: "output".to_string(), data: Some(output.clone()), node_id: node.id.clone(), node_type: None, result: Some(output.clone()) };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    sse::send_json(log_data, sender)?;
+
+    Ok((logs, output))
+}
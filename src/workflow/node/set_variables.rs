@@ -0,0 +1,41 @@
+use std::convert::Infallible;
+
+use anyhow::anyhow;
+use axum::response::sse::Event;
+use chrono::Utc;
+use serde_json::{Map, Value};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::{
+    super::{
+        context::Context, model::{Log, LogData, Node}, sse
+    }, transform
+};
+
+/// 写入执行变量。variables 配置为 JSON 对象，字符串值按 jq 表达式对 input 求值
+/// （字面量写作 `"\"text\""`、`1` 等），其他 JSON 值原样写入。输入原样透传给下游节点。
+pub async fn execute(node: &Node, ctx: &Context, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
+    let input = node.config.get("input").map(|v| v.to_string()).unwrap_or_default();
+    let variables = node.config.get("variables").map(|v| v.as_str()).unwrap_or("{}");
+    let variables: Map<String, Value> = serde_json::from_str(variables).map_err(|e| anyhow!("variables 配置不是合法的 JSON 对象: {}", e))?;
+
+    let value = serde_json::from_str(&input).unwrap_or(Value::String(input.clone()));
+
+    let mut assigned = Map::new();
+    for (name, definition) in variables {
+        let result = match definition {
+            Value::String(expression) => {
+                let mut results = transform::evaluate(&expression, value.clone(), &ctx.variables()).map_err(|e| anyhow!("变量 {} 计算失败: {}", name, e))?;
+                if results.len() == 1 { results.remove(0) } else { Value::Array(results) }
+            }
+            literal => literal,
+        };
+        ctx.set_variable(name.clone(), result.clone());
+        assigned.insert(name, result);
+    }
+
+    let log_data = LogData { kind: "variables".to_string(), data: Some(Value::Object(assigned).to_string()), node_id: node.id.clone(), node_type: Some(node.kind.clone()), result: None };
+    sse::send_json(log_data.clone(), sender)?;
+
+    Ok((vec![Log { timestamp: Utc::now(), data: log_data }], input))
+}
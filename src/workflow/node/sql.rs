@@ -0,0 +1,191 @@
+use std::convert::Infallible;
+
+use anyhow::anyhow;
+use axum::response::sse::Event;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::super::{
+    model::{Log, LogData, Node}, sse::send_json
+};
+
+// postgresql、mysql、sqlite 节点共用的结果格式与日志输出，保证各 SQL 节点行为一致
+
+/// 结果列，type 为数据库返回的类型名
+#[derive(Serialize)]
+pub struct Column {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+/// 查询参数，按顺序绑定到 $1、$2…（mysql 为 ?，sqlite 为 ? 或 ?N）。
+/// params 配置为 JSON 数组，元素为 {"type": "int8", "value": "${input}"}，纯字符串等同于 text 类型
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ParameterConfig {
+    Typed {
+        #[serde(rename = "type", default)]
+        kind: String,
+        #[serde(default)]
+        value: Value,
+    },
+    Plain(Value),
+}
+
+#[derive(Deserialize)]
+#[serde(from = "ParameterConfig")]
+pub struct Parameter {
+    /// 小写类型名，未指定时为 text
+    pub kind: String,
+    pub value: Value,
+}
+
+impl From<ParameterConfig> for Parameter {
+    fn from(config: ParameterConfig) -> Self {
+        match config {
+            ParameterConfig::Typed { kind, value } => Parameter { kind: if kind.trim().is_empty() { "text".to_string() } else { kind.trim().to_lowercase() }, value },
+            ParameterConfig::Plain(value) => Parameter { kind: "text".to_string(), value },
+        }
+    }
+}
+
+pub fn parameters(node: &Node) -> anyhow::Result<Vec<Parameter>> {
+    let Some(config) = node.config.get("params").filter(|v| !v.trim().is_empty()) else {
+        return Ok(vec![]);
+    };
+    serde_json::from_str(config).map_err(|e| anyhow!("params 配置不是合法的 JSON 数组: {}", e))
+}
+
+impl Parameter {
+    /// 非文本类型的空字符串视为 NULL，便于模板渲染为空时传入 NULL
+    fn is_null(&self) -> bool {
+        self.value.is_null() || self.value.as_str().is_some_and(|v| v.trim().is_empty())
+    }
+
+    pub fn text(&self) -> Option<String> {
+        match &self.value {
+            Value::Null => None,
+            Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        }
+    }
+
+    pub fn integer(&self) -> anyhow::Result<Option<i64>> {
+        if self.is_null() {
+            return Ok(None);
+        }
+        match &self.value {
+            Value::Number(n) => n.as_i64(),
+            Value::String(s) => s.trim().parse().ok(),
+            Value::Bool(b) => Some(*b as i64),
+            _ => None,
+        }
+        .map(Some)
+        .ok_or_else(|| anyhow!("参数 {} 不是合法的整数", self.value))
+    }
+
+    pub fn float(&self) -> anyhow::Result<Option<f64>> {
+        if self.is_null() {
+            return Ok(None);
+        }
+        match &self.value {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+        .map(Some)
+        .ok_or_else(|| anyhow!("参数 {} 不是合法的数字", self.value))
+    }
+
+    pub fn boolean(&self) -> anyhow::Result<Option<bool>> {
+        if self.is_null() {
+            return Ok(None);
+        }
+        match &self.value {
+            Value::Bool(b) => Some(*b),
+            Value::Number(n) => n.as_i64().map(|n| n != 0),
+            Value::String(s) => match s.trim().to_lowercase().as_str() {
+                "true" | "1" | "yes" | "on" => Some(true),
+                "false" | "0" | "no" | "off" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+        .map(Some)
+        .ok_or_else(|| anyhow!("参数 {} 不是合法的布尔值", self.value))
+    }
+
+    /// 字符串按 JSON 解析，无法解析时作为 JSON 字符串
+    pub fn json(&self) -> Option<Value> {
+        match &self.value {
+            Value::Null => None,
+            Value::String(s) => Some(serde_json::from_str(s).unwrap_or_else(|_| Value::String(s.clone()))),
+            other => Some(other.clone()),
+        }
+    }
+}
+
+/// 查询结果 JSON：success、message、columns、data、row_count，每行按列名转换为对象
+pub fn query_result(columns: Vec<Column>, rows: Vec<Vec<Value>>) -> Value {
+    let data: Vec<Value> = rows.into_iter().map(|row| row_object(&columns, row)).collect();
+    let message = if data.is_empty() { "查询执行成功，无结果返回".to_string() } else { format!("查询执行成功，返回 {} 条记录", data.len()) };
+    json!({
+        "success": true,
+        "message": message,
+        "columns": columns,
+        "data": data,
+        "row_count": data.len()
+    })
+}
+
+/// 按列名把一行转换为对象
+pub fn row_object(columns: &[Column], row: Vec<Value>) -> Value {
+    Value::Object(columns.iter().map(|column| column.name.clone()).zip(row).collect::<Map<_, _>>())
+}
+
+/// INSERT、UPDATE、DELETE 等不返回结果集的语句，affected_rows 为影响的行数
+pub fn affected_result(affected_rows: u64) -> Value {
+    json!({
+        "success": true,
+        "message": format!("执行成功，影响 {} 行", affected_rows),
+        "columns": [],
+        "data": [],
+        "row_count": 0,
+        "affected_rows": affected_rows
+    })
+}
+
+pub fn datetime(value: chrono::NaiveDateTime) -> Value {
+    json!(value.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+pub fn date(value: chrono::NaiveDate) -> Value {
+    json!(value.format("%Y-%m-%d").to_string())
+}
+
+/// 二进制数据以 base64 字符串输出
+pub fn bytes(value: &[u8]) -> Value {
+    json!(STANDARD.encode(value))
+}
+
+/// 记录 `<prefix>-info` 等日志
+pub fn log(node: &Node, kind: String, message: String, logs: &mut Vec<Log>, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) {
+    let log_data = LogData { kind, data: Some(message), node_id: node.id.clone(), node_type: None, result: None };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    send_json(log_data, sender).unwrap();
+}
+
+/// 记录查询结果并输出；失败或超时时返回错误，节点失败并沿 error 连接点继续
+pub fn output(
+    node: &Node, result: Result<anyhow::Result<Value>, tokio::time::error::Elapsed>, logs: &mut Vec<Log>, sender: &Option<UnboundedSender<Result<Event, Infallible>>>,
+) -> anyhow::Result<String> {
+    let result = result.map_err(|_| anyhow!("查询超时"))?.map_err(|e| anyhow!("查询执行失败: {}", e))?.to_string();
+    let log_data = LogData { kind: "output".to_string(), data: Some(result.clone()), node_id: node.id.clone(), node_type: None, result: Some(result.clone()) };
+    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+    send_json(log_data, sender)?;
+    Ok(result)
+}
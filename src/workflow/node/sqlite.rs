@@ -0,0 +1,145 @@
+use std::{convert::Infallible, time::Duration};
+
+use anyhow::{anyhow, bail};
+use axum::response::sse::Event;
+use rusqlite::{
+    Connection, params_from_iter, types::{Value as SqliteValue, ValueRef}
+};
+use serde_json::{Value, json};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::{
+    super::model::{Log, Node}, sql::{self, Column, Parameter}
+};
+
+pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
+    let mut logs = vec![];
+
+    // 数据库文件路径，不存在时自动创建
+    let path = node.config.get("path").map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let query = node.config.get("query").filter(|v| !v.trim().is_empty()).cloned();
+
+    let (Some(path), Some(query)) = (path, query) else {
+        let message = if node.config.get("path").is_none_or(|v| v.trim().is_empty()) { "数据库文件路径为空" } else { "SQL查询为空" };
+        bail!(message);
+    };
+
+    sql::log(
+        node,
+        "sqlite-info".to_string(),
+        format!("正在打开 SQLite 数据库: {}", path),
+        &mut logs,
+        sender,
+    );
+
+    // rusqlite 为同步接口，在阻塞线程池中执行
+    let params = sql::parameters(node);
+    let task = tokio::task::spawn_blocking(move || execute_query(&path, &query, params));
+    let query_result = tokio::time::timeout(Duration::from_secs(30), async {
+        task.await.map_err(|e| anyhow!(e))?
+    })
+    .await;
+    let output = sql::output(node, query_result, &mut logs, sender)?;
+
+    Ok((logs, output))
+}
+
+fn execute_query(path: &str, query: &str, params: anyhow::Result<Vec<Parameter>>) -> anyhow::Result<Value> {
+    let values = params?.iter().map(bind).collect::<anyhow::Result<Vec<_>>>()?;
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(Duration::from_secs(5))?;
+    let mut statement = conn.prepare(query)?;
+    if statement.column_count() == 0 {
+        return Ok(sql::affected_result(
+            statement.execute(params_from_iter(values))? as u64,
+        ));
+    }
+    let columns: Vec<Column> = statement.columns().iter().map(|column| Column { name: column.name().to_string(), kind: column.decl_type().unwrap_or_default().to_lowercase() }).collect();
+
+    let mut data_rows = Vec::new();
+    let mut rows = statement.query(params_from_iter(values))?;
+    while let Some(row) = rows.next()? {
+        let mut values = Vec::new();
+        for i in 0..columns.len() {
+            let value = match row.get_ref(i)? {
+                ValueRef::Null => Value::Null,
+                ValueRef::Integer(v) => json!(v),
+                ValueRef::Real(v) => json!(v),
+                ValueRef::Text(v) => json!(String::from_utf8_lossy(v)),
+                ValueRef::Blob(v) => sql::bytes(v),
+            };
+            values.push(value);
+        }
+        data_rows.push(values);
+    }
+
+    Ok(sql::query_result(columns, data_rows))
+}
+
+fn bind(param: &Parameter) -> anyhow::Result<SqliteValue> {
+    let value = match param.kind.as_str() {
+        "int" | "integer" | "bigint" | "int2" | "int4" | "int8" => param.integer()?.map(SqliteValue::Integer),
+        "real" | "float" | "double" | "float4" | "float8" => param.float()?.map(SqliteValue::Real),
+        "bool" | "boolean" => param.boolean()?.map(|v| SqliteValue::Integer(v as i64)),
+        "json" => param.json().map(|v| SqliteValue::Text(v.to_string())),
+        _ => param.text().map(SqliteValue::Text),
+    };
+    Ok(value.unwrap_or(SqliteValue::Null))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(config: Value) -> Node {
+        serde_json::from_value(json!({ "id": "sqlite", "type": "sqlite", "position": { "x": 0, "y": 0 }, "config": config })).unwrap()
+    }
+
+    async fn run(path: &str, query: &str, params: &str) -> anyhow::Result<String> {
+        execute(
+            &node(json!({ "path": path, "query": query, "params": params })),
+            &None,
+        )
+        .await
+        .map(|(_, output)| output)
+    }
+
+    #[tokio::test]
+    async fn runs_queries_and_fails_on_errors() {
+        let path = std::env::temp_dir().join(format!("n2s-{}.db", uuid::Uuid::new_v4().simple()));
+        let path = path.to_string_lossy().to_string();
+
+        let output = run(&path, "create table t (id integer, name text)", "").await.unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&output).unwrap()["affected_rows"],
+            json!(0)
+        );
+        run(
+            &path,
+            "insert into t values (?1, ?2)",
+            r#"[{"type": "int", "value": "1"}, "a"]"#,
+        )
+        .await
+        .unwrap();
+        let output = run(&path, "select id, name from t where id = ?", "[1]").await.unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&output).unwrap()["data"],
+            json!([{ "id": 1, "name": "a" }])
+        );
+
+        let error = run(&path, "select * from missing", "").await.unwrap_err().to_string();
+        assert_eq!(error, "查询执行失败: no such table: missing");
+        let error = run(&path, "select ?", r#"[{"type": "int", "value": "x"}]"#).await.unwrap_err().to_string();
+        assert_eq!(error, r#"查询执行失败: 参数 "x" 不是合法的整数"#);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            execute(&node(json!({ "query": "select 1" })), &None).await.unwrap_err().to_string(),
+            "数据库文件路径为空"
+        );
+        assert_eq!(
+            execute(&node(json!({ "path": path })), &None).await.unwrap_err().to_string(),
+            "SQL查询为空"
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use std::{collections::HashMap, convert::Infallible};
+
+use anyhow::anyhow;
+use axum::response::sse::Event;
+use chrono::Utc;
+use jaq_core::{
+    Compiler, Ctx, RcIter, load::{Arena, File, Loader}
+};
+use jaq_json::Val;
+use serde_json::Value;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::super::{
+    context::Context, model::{Log, LogData, Node}, sse
+};
+
+pub async fn execute(node: &Node, ctx: &Context, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
+    let expression = node.config.get("expression").map(|v| v.to_string()).unwrap_or(".".to_string());
+    let input = node.config.get("input").map(|v| v.to_string()).unwrap_or_default();
+    // 默认像 jq -r 一样直接输出字符串结果
+    let raw = node.config.get("raw").map(|v| v != "false").unwrap_or(true);
+
+    // 输入不是合法 JSON 时按普通字符串处理
+    let value = serde_json::from_str(&input).unwrap_or(Value::String(input));
+
+    let results = evaluate(&expression, value, &ctx.variables()).map_err(|e| anyhow!("transform 表达式执行失败: {}", e))?;
+    let output = match results.as_slice() {
+        [] => "null".to_string(),
+        [Value::String(s)] if raw => s.clone(),
+        [value] => value.to_string(),
+        values => Value::Array(values.to_vec()).to_string(),
+    };
+
+    let log_data = LogData { kind: "output".to_string(), data: Some(output.clone()), node_id: node.id.clone(), node_type: None, result: Some(output.clone()) };
+    sse::send_json(log_data.clone(), sender)?;
+
+    Ok((vec![Log { timestamp: Utc::now(), data: log_data }], output))
+}
+
+/// 对输入执行 jq 表达式，返回所有输出值；执行变量可通过 $vars 访问
+pub fn evaluate(expression: &str, input: Value, variables: &HashMap<String, Value>) -> anyhow::Result<Vec<Value>> {
+    let program = File { code: expression, path: () };
+    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+    let arena = Arena::default();
+
+    let modules = loader.load(&arena, program).map_err(|errs| {
+        let messages: Vec<String> = errs
+            .into_iter()
+            .flat_map(|(_, err)| match err {
+                jaq_core::load::Error::Io(errs) => errs.into_iter().map(|(path, e)| format!("无法加载模块 {}: {}", path, e)).collect::<Vec<_>>(),
+                jaq_core::load::Error::Lex(errs) => errs.into_iter().map(|(expect, at)| format!("{}: 期望 {}", position(expression, at), expect.as_str())).collect(),
+                jaq_core::load::Error::Parse(errs) => errs.into_iter().map(|(expect, at)| format!("{}: 期望 {}", position(expression, at), expect.as_str())).collect(),
+            })
+            .collect();
+        anyhow!("表达式语法错误: {}", messages.join("; "))
+    })?;
+
+    let filter = Compiler::default().with_global_vars(["$vars"]).with_funs(jaq_std::funs().chain(jaq_json::funs())).compile(modules).map_err(|errs| {
+        let messages: Vec<String> = errs
+            .into_iter()
+            .flat_map(|(_, errs)| {
+                errs.into_iter().map(|(name, undefined)| {
+                    format!(
+                        "{}: 未定义的{} {}",
+                        position(expression, name),
+                        undefined.as_str(),
+                        name
+                    )
+                })
+            })
+            .collect();
+        anyhow!("表达式编译错误: {}", messages.join("; "))
+    })?;
+
+    let inputs = RcIter::new(core::iter::empty());
+    let mut results = Vec::new();
+    let vars = Val::from(serde_json::to_value(variables)?);
+    for result in filter.run((Ctx::new([vars], &inputs), Val::from(input))) {
+        match result {
+            Ok(value) => results.push(Value::from(value)),
+            Err(e) => return Err(anyhow!("{}", e)),
+        }
+    }
+    Ok(results)
+}
+
+/// 将表达式中的片段转换为 "第 x 行第 y 列" 的位置描述
+fn position(expression: &str, at: &str) -> String {
+    let offset = (at.as_ptr() as usize).saturating_sub(expression.as_ptr() as usize).min(expression.len());
+    let before = &expression[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map(|s| s.chars().count()).unwrap_or(0) + 1;
+    format!("第 {} 行第 {} 列", line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn evaluates_filter() {
+        let input = json!({ "users": [{ "name": "a", "age": 30 }, { "name": "b", "age": 17 }] });
+        assert_eq!(
+            evaluate(
+                "[.users[] | select(.age >= 18) | .name]",
+                input,
+                &HashMap::new()
+            )
+            .unwrap(),
+            vec![json!(["a"])]
+        );
+    }
+
+    #[test]
+    fn reads_execution_variables() {
+        let variables = HashMap::from([("limit".to_string(), json!(2))]);
+        assert_eq!(
+            evaluate(".[:$vars.limit]", json!([1, 2, 3]), &variables).unwrap(),
+            vec![json!([1, 2])]
+        );
+    }
+
+    #[test]
+    fn returns_every_output() {
+        assert_eq!(
+            evaluate(".[] | . * 2", json!([1, 2, 3]), &HashMap::new()).unwrap(),
+            vec![json!(2), json!(4), json!(6)]
+        );
+        assert_eq!(
+            evaluate("empty", json!(null), &HashMap::new()).unwrap(),
+            Vec::<Value>::new()
+        );
+    }
+
+    #[test]
+    fn reports_parse_error_position() {
+        let error = evaluate(".a |\n  .b | [", json!({}), &HashMap::new()).unwrap_err().to_string();
+        assert_eq!(
+            error,
+            "表达式语法错误: 第 2 行第 9 列: 期望 closing bracket"
+        );
+    }
+
+    #[test]
+    fn reports_undefined_function_position() {
+        let error = evaluate(".a | nosuch(1)", json!({}), &HashMap::new()).unwrap_err().to_string();
+        assert!(
+            error.starts_with("表达式编译错误: 第 1 行第 6 列: 未定义的"),
+            "{}",
+            error
+        );
+        assert!(error.ends_with("nosuch"), "{}", error);
+    }
+
+    #[test]
+    fn reports_runtime_error() {
+        assert!(evaluate(".a + 1", json!({ "a": "text" }), &HashMap::new()).is_err());
+        assert!(evaluate("error(\"失败\")", json!(null), &HashMap::new()).unwrap_err().to_string().contains("失败"));
+    }
+}
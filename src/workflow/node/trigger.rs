@@ -0,0 +1,20 @@
+use std::convert::Infallible;
+
+use axum::response::sse::Event;
+use chrono::Utc;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::super::{
+    context::Context, model::{Log, LogData, Node}, sse
+};
+
+/// 触发器节点：由该节点触发时输出触发数据（JSON），手动执行时与输入节点一样输出 input
+pub async fn execute(node: &Node, ctx: &Context, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
+    let output = match &ctx.trigger {
+        Some(event) if event.node_id == node.id => event.data.to_string(),
+        _ => node.config.get("input").cloned().unwrap_or_default(),
+    };
+    let log_data = LogData { kind: "trigger".to_string(), data: Some(output.clone()), node_id: node.id.clone(), node_type: Some(node.kind.clone()), result: None };
+    sse::send_json(log_data.clone(), sender)?;
+    Ok((vec![Log { timestamp: Utc::now(), data: log_data }], output))
+}
@@ -0,0 +1,116 @@
+use std::{
+    convert::Infallible, sync::Arc, time::{Duration, Instant}
+};
+
+use anyhow::{anyhow, bail};
+use axum::response::sse::Event;
+use chrono::Utc;
+use futures::{SinkExt, StreamExt};
+use serde_json::Value;
+use tokio::{net::TcpStream, sync::mpsc::UnboundedSender};
+use tokio_tungstenite::{
+    Connector, MaybeTlsStream, WebSocketStream, tungstenite::{Message, client::IntoClientRequest}
+};
+
+use super::{
+    super::{
+        binary::Binary, context::Context, model::{Log, LogData, Node}, sse::send_json, tls
+    }, http, transform
+};
+
+pub type WebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// 连接 url（ws:// 或 wss://），headers 配置每行一个 "名称: 值"，与 websocket 触发器共用
+pub async fn connect(node: &Node, timeout: Duration) -> anyhow::Result<WebSocket> {
+    let url = node.config.get("url").map(|v| v.trim()).filter(|v| !v.is_empty()).ok_or_else(|| anyhow!("url 为空"))?;
+    let mut request = url.into_client_request().map_err(|e| anyhow!("无效的 WebSocket 地址 {}: {}", url, e))?;
+    request.headers_mut().extend(http::headers(node)?);
+
+    let config = tls::client_config(None)?;
+
+    let connecting = tokio_tungstenite::connect_async_tls_with_config(
+        request,
+        None,
+        false,
+        Some(Connector::Rustls(Arc::new(config))),
+    );
+    let (socket, _) = tokio::time::timeout(timeout, connecting).await.map_err(|_| anyhow!("连接 {} 超时", url))?.map_err(|e| anyhow!("连接 {} 失败: {}", url, e))?;
+    Ok(socket)
+}
+
+/// messages 为要发送的消息：JSON 数组时逐条发送（非字符串的元素以 JSON 文本发送），否则作为一条文本消息
+pub fn messages(node: &Node) -> Vec<String> {
+    let Some(config) = node.config.get("messages").filter(|v| !v.trim().is_empty()) else {
+        return vec![];
+    };
+    match serde_json::from_str::<Value>(config) {
+        Ok(Value::Array(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(s) => s,
+                other => other.to_string(),
+            })
+            .collect(),
+        _ => vec![config.clone()],
+    }
+}
+
+/// 收到的消息：文本为 JSON 时解析为 JSON，二进制消息保存为二进制数据；控制帧返回 None
+pub fn message_value(message: Message) -> anyhow::Result<Option<Value>> {
+    Ok(match message {
+        Message::Text(text) => Some(serde_json::from_str(text.as_str()).unwrap_or_else(|_| Value::String(text.to_string()))),
+        Message::Binary(bytes) => Some(serde_json::to_value(Binary::save(&bytes, None, None)?)?),
+        _ => None,
+    })
+}
+
+/// 连接后发送消息并收集回复，直到收到 count 条（默认 1）、until 表达式（jq，以回复为输入）为 true、
+/// 服务端关闭连接或超过 timeout 秒（默认 30），输出回复数组
+pub async fn execute(node: &Node, ctx: &Context, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
+    let timeout = match node.config.get("timeout").map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        Some(v) => v.parse::<f64>().ok().filter(|v| *v > 0.0).map(Duration::from_secs_f64).ok_or_else(|| anyhow!("timeout 不是合法的秒数: {}", v))?,
+        None => Duration::from_secs(30),
+    };
+    let count = match node.config.get("count").map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        Some(v) => v.parse::<usize>().map_err(|_| anyhow!("count 不是合法的整数: {}", v))?,
+        None => 1,
+    };
+    let until = node.config.get("until").map(|v| v.trim()).filter(|v| !v.is_empty());
+    let deadline = Instant::now() + timeout;
+
+    let mut socket = connect(node, timeout).await?;
+    for message in messages(node) {
+        socket.send(Message::text(message)).await.map_err(|e| anyhow!("发送消息失败: {}", e))?;
+    }
+
+    let mut replies = Vec::new();
+    while count == 0 || replies.len() < count {
+        let message = match tokio::time::timeout_at(deadline.into(), socket.next()).await {
+            Err(_) | Ok(None) => break,
+            Ok(Some(message)) => message.map_err(|e| anyhow!("接收消息失败: {}", e))?,
+        };
+        if message.is_close() {
+            break;
+        }
+        let Some(reply) = message_value(message)? else {
+            continue;
+        };
+        let done = match until {
+            Some(expression) => transform::evaluate(expression, reply.clone(), &ctx.variables()).map_err(|e| anyhow!("until 表达式执行失败: {}", e))?.iter().any(|v| v.as_bool() == Some(true)),
+            None => false,
+        };
+        replies.push(reply);
+        if done {
+            break;
+        }
+    }
+    let _ = socket.close(None).await;
+    if replies.is_empty() && node.config.get("allowEmpty").is_none_or(|v| v.trim() != "true") {
+        bail!("{} 秒内没有收到回复", timeout.as_secs_f64());
+    }
+
+    let output = Value::Array(replies).to_string();
+    let log_data = LogData { kind: "output".to_string(), data: Some(output.clone()), node_id: node.id.clone(), node_type: None, result: Some(output.clone()) };
+    send_json(log_data.clone(), sender)?;
+    Ok((vec![Log { timestamp: Utc::now(), data: log_data }], output))
+}
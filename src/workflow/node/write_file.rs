@@ -5,7 +5,7 @@ use chrono::Utc;
 use tokio::sync::mpsc::UnboundedSender;
 
 use super::super::{
-    model::{Log, LogData, Node}, sse::send_json
+    binary::Binary, model::{Log, LogData, Node}, sse::send_json
 };
 
 pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event, Infallible>>>) -> anyhow::Result<(Vec<Log>, String)> {
@@ -14,31 +14,37 @@ pub async fn execute(node: &Node, sender: &Option<UnboundedSender<Result<Event,
     let path = node.config.get("path");
     let content = node.config.get("content");
 
-    if path.is_none() || content.is_none() {
-        let log_data = LogData { kind: "write-file-error".to_string(), data: Some("path 或 content 为空".to_string()), node_id: node.id.clone(), node_type: None, result: None };
-        logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
-        send_json(log_data, sender).unwrap();
-    } else {
-        let path = path.unwrap();
-        let content = content.unwrap();
-        if let Some(parent) = Path::new(path).parent() {
-            if !parent.exists() {
+    match (path, content) {
+        (Some(path), Some(content)) => {
+            if let Some(parent) = Path::new(path).parent()
+                && !parent.exists()
+            {
                 fs::create_dir_all(parent)?;
             }
-        }
-        match fs::write(path, content) {
-            Ok(_) => {
-                let log_data =
-                    LogData { kind: "output".to_string(), data: Some("文件写入成功".to_string()), node_id: node.id.clone(), node_type: None, result: Some("文件写入成功".to_string()) };
-                logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
-                send_json(log_data, sender).unwrap();
-            }
-            Err(e) => {
-                let log_data = LogData { kind: "output".to_string(), data: Some(format!("error: {}", e)), node_id: node.id.clone(), node_type: None, result: None };
-                logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
-                send_json(log_data, sender).unwrap();
+            // 内容为二进制引用时写入原始字节
+            let written = match Binary::parse(content) {
+                Some(binary) => binary.read().and_then(|bytes| Ok(fs::write(path, bytes)?)),
+                None => fs::write(path, content).map_err(|e| e.into()),
+            };
+            match written {
+                Ok(_) => {
+                    let log_data =
+                        LogData { kind: "output".to_string(), data: Some("文件写入成功".to_string()), node_id: node.id.clone(), node_type: None, result: Some("文件写入成功".to_string()) };
+                    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+                    send_json(log_data, sender).unwrap();
+                }
+                Err(e) => {
+                    let log_data = LogData { kind: "output".to_string(), data: Some(format!("error: {}", e)), node_id: node.id.clone(), node_type: None, result: None };
+                    logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+                    send_json(log_data, sender).unwrap();
+                }
             }
         }
+        _ => {
+            let log_data = LogData { kind: "write-file-error".to_string(), data: Some("path 或 content 为空".to_string()), node_id: node.id.clone(), node_type: None, result: None };
+            logs.push(Log { timestamp: Utc::now(), data: log_data.clone() });
+            send_json(log_data, sender).unwrap();
+        }
     }
     Ok((logs, output))
 }
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+
+use super::binary::Binary;
+
+/// 工作流参数定义
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorkflowParameter {
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub kind: ParameterType,
+    #[serde(default)]
+    pub required: bool,
+    pub default: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterType {
+    #[default]
+    String,
+    Number,
+    Boolean,
+    Json,
+    /// base64 编码的文件内容，保存为二进制数据后以其文件路径作为参数值，与其他二进制数据一样过期清理
+    File,
+    /// base64 或 data URL 编码的内容，保存为二进制数据后以其引用作为参数值
+    Binary,
+}
+
+/// 按参数定义校验触发时传入的值并填充默认值，返回实际生效的参数。
+/// 未声明的值会被忽略。
+pub fn resolve(parameters: &[WorkflowParameter], values: &HashMap<String, String>) -> Result<HashMap<String, String>, String> {
+    let mut resolved = HashMap::new();
+    let mut errors = Vec::new();
+    for parameter in parameters {
+        let provided = values.get(&parameter.name).filter(|v| !v.is_empty());
+        let Some(value) = provided.or(parameter.default.as_ref()) else {
+            if parameter.required {
+                errors.push(format!("缺少必填参数 {}", parameter.name));
+            }
+            continue;
+        };
+        // 默认值视为已校验过的可信配置，文件类型的默认值即为文件路径
+        let is_default = provided.is_none();
+        match convert(parameter, value, is_default) {
+            Ok(value) => {
+                resolved.insert(parameter.name.clone(), value);
+            }
+            Err(e) => errors.push(format!("参数 {} {}", parameter.name, e)),
+        }
+    }
+    if errors.is_empty() { Ok(resolved) } else { Err(errors.join("; ")) }
+}
+
+fn convert(parameter: &WorkflowParameter, value: &str, is_default: bool) -> Result<String, String> {
+    match parameter.kind {
+        ParameterType::String => Ok(value.to_string()),
+        ParameterType::Number => value.trim().parse::<f64>().map(|_| value.trim().to_string()).map_err(|_| format!("不是合法的数字: {}", value)),
+        ParameterType::Boolean => match value.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Ok("true".to_string()),
+            "false" | "0" | "no" | "off" => Ok("false".to_string()),
+            _ => Err(format!("不是合法的布尔值: {}", value)),
+        },
+        ParameterType::Json => serde_json::from_str::<serde_json::Value>(value).map(|v| v.to_string()).map_err(|e| format!("不是合法的 JSON: {}", e)),
+        ParameterType::File if is_default => Ok(value.to_string()),
+        ParameterType::File => save_upload(&parameter.name, value),
+        ParameterType::Binary if is_default => Ok(value.to_string()),
+        ParameterType::Binary => Binary::decode(value, Some(&parameter.name)).map(|binary| binary.to_output()).map_err(|e| e.to_string()),
+    }
+}
+
+/// 保存 base64（或 data URL）编码的文件，返回保存路径
+fn save_upload(name: &str, value: &str) -> Result<String, String> {
+    let encoded = match value.split_once(";base64,") {
+        Some((prefix, data)) if prefix.starts_with("data:") => data,
+        _ => value,
+    };
+    let content = STANDARD.decode(encoded.trim()).map_err(|e| format!("不是合法的 base64 文件内容: {}", e))?;
+    let binary = Binary::save(&content, Some(name), None).map_err(|e| format!("保存文件失败: {}", e))?;
+    Ok(binary.path().to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn normalizes_values_and_ignores_undeclared() {
+        let parameters: Vec<WorkflowParameter> = serde_json::from_value(json!([
+            { "name": "name", "required": true },
+            { "name": "count", "type": "number" },
+            { "name": "enabled", "type": "boolean" },
+            { "name": "data", "type": "json" },
+        ]))
+        .unwrap();
+        let resolved = resolve(
+            &parameters,
+            &values(&[("name", " a "), ("count", " 1.5 "), ("enabled", "YES"), ("data", r#"{ "a": [1, 2] }"#), ("other", "x")]),
+        )
+        .unwrap();
+        assert_eq!(
+            resolved,
+            values(&[("name", " a "), ("count", "1.5"), ("enabled", "true"), ("data", r#"{"a":[1,2]}"#)])
+        );
+    }
+
+    #[test]
+    fn fills_defaults_for_missing_and_empty_values() {
+        let parameters: Vec<WorkflowParameter> = serde_json::from_value(json!([
+            { "name": "limit", "type": "number", "required": true, "default": "10" },
+            { "name": "path", "type": "file", "default": "/tmp/a.txt" },
+            { "name": "optional" },
+        ]))
+        .unwrap();
+        let resolved = resolve(&parameters, &values(&[("limit", "")])).unwrap();
+        assert_eq!(resolved, values(&[("limit", "10"), ("path", "/tmp/a.txt")]));
+    }
+
+    #[test]
+    fn saves_uploads_as_binaries() {
+        let parameters: Vec<WorkflowParameter> = serde_json::from_value(json!([{ "name": "report.txt", "type": "file" }])).unwrap();
+        let resolved = resolve(
+            &parameters,
+            &values(&[("report.txt", "data:text/plain;base64,aGVsbG8=")]),
+        )
+        .unwrap();
+        let path = std::path::PathBuf::from(&resolved["report.txt"]);
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello");
+        let binary = Binary::parse(&std::fs::read_to_string(path.with_extension("json")).unwrap()).unwrap();
+        assert_eq!((binary.file_name.as_str(), binary.size), ("report.txt", 5));
+        assert_eq!(binary.path(), path);
+    }
+
+    #[test]
+    fn collects_all_errors() {
+        let parameters: Vec<WorkflowParameter> = serde_json::from_value(json!([
+            { "name": "name", "required": true },
+            { "name": "count", "type": "number" },
+            { "name": "enabled", "type": "boolean" },
+            { "name": "data", "type": "json" },
+            { "name": "file", "type": "file" },
+        ]))
+        .unwrap();
+        let error = resolve(
+            &parameters,
+            &values(&[("count", "abc"), ("enabled", "maybe"), ("data", "{"), ("file", "not base64!")]),
+        )
+        .unwrap_err();
+        let errors: Vec<&str> = error.split("; ").collect();
+        assert_eq!(errors.len(), 5);
+        assert_eq!(errors[0], "缺少必填参数 name");
+        assert_eq!(errors[1], "参数 count 不是合法的数字: abc");
+        assert_eq!(errors[2], "参数 enabled 不是合法的布尔值: maybe");
+        assert!(errors[3].starts_with("参数 data 不是合法的 JSON"));
+        assert!(errors[4].starts_with("参数 file 不是合法的 base64 文件内容"));
+    }
+}
@@ -0,0 +1,40 @@
+use tokio::sync::mpsc;
+
+use super::model::{Log, Node};
+
+/// 流式节点（stream=true）逐条产生的输出。通道容量有限，下游处理较慢时上游暂停读取，
+/// 大结果集和大文件不会整体载入内存
+pub type Items = mpsc::Receiver<anyhow::Result<String>>;
+
+/// 通道中缓存的条数
+pub const CAPACITY: usize = 64;
+
+/// 执行记录中保留最后多少条数据及其下游的日志
+pub const LOG_ITEMS: usize = 20;
+
+/// 执行记录中每条日志保留的最大字符数
+const PREVIEW_CHARS: usize = 1000;
+
+/// 支持流式输出的节点：postgresql 按游标分页读取，read-file 逐行读取
+pub fn is_streaming(node: &Node) -> bool {
+    matches!(node.kind.as_str(), "postgresql" | "read-file") && node.config.get("stream").is_some_and(|v| v.trim() == "true")
+}
+
+pub fn channel() -> (mpsc::Sender<anyhow::Result<String>>, Items) {
+    mpsc::channel(CAPACITY)
+}
+
+/// 截断过长的文本，保留开头部分并注明原始长度
+pub fn preview(text: &str) -> String {
+    match text.char_indices().nth(PREVIEW_CHARS) {
+        Some((index, _)) => format!("{}…（共 {} 字节，已截断）", &text[..index], text.len()),
+        None => text.to_string(),
+    }
+}
+
+/// 流式执行时下游节点的日志只保留预览，避免执行记录随条数膨胀
+pub fn preview_log(mut log: Log) -> Log {
+    log.data.data = log.data.data.as_deref().map(preview);
+    log.data.result = log.data.result.as_deref().map(preview);
+    log
+}
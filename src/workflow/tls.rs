@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use tokio_rustls::rustls::{
+    ClientConfig, RootCertStore, crypto::ring, pki_types::{CertificateDer, pem::PemObject}
+};
+
+/// TLS 客户端配置：使用 ring 与 webpki 内置的根证书，root_cert 为额外信任的 CA 证书（PEM 文件）
+pub fn client_config(root_cert: Option<&str>) -> anyhow::Result<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    if let Some(path) = root_cert {
+        for cert in CertificateDer::pem_file_iter(path).map_err(|e| anyhow!("读取 CA 证书 {} 失败: {}", path, e))? {
+            roots.add(cert?)?;
+        }
+    }
+    Ok(ClientConfig::builder_with_provider(Arc::new(ring::default_provider())).with_safe_default_protocol_versions()?.with_root_certificates(roots).with_no_client_auth())
+}
@@ -0,0 +1,376 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, bail};
+use log::{error, info};
+use mail_parser::{Address, MessageParser, MimeHeaders};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader}, net::TcpStream
+};
+use tokio_rustls::{TlsConnector, client::TlsStream, rustls::pki_types::ServerName};
+
+use super::super::{binary::Binary, context::TriggerEvent, model::Node, run_trigger, tls};
+
+const DEFAULT_INTERVAL_SECS: u64 = 60;
+
+trait Stream: AsyncRead+AsyncWrite+Unpin+Send {}
+impl<T: AsyncRead+AsyncWrite+Unpin+Send> Stream for T {}
+
+/// 已处理到的位置。UIDVALIDITY 变化说明邮箱被重建，原有 UID 失效
+#[derive(Serialize, Deserialize, Default)]
+struct State {
+    #[serde(rename = "uidValidity")]
+    uid_validity: u32,
+    #[serde(rename = "lastUid")]
+    last_uid: u32,
+}
+
+/// 定时轮询 IMAP 文件夹，每封新邮件执行一次工作流
+pub async fn listen(workflow_id: String, node: Node) {
+    let interval = node.config.get("interval").and_then(|v| v.trim().parse::<u64>().ok()).filter(|v| *v > 0).unwrap_or(DEFAULT_INTERVAL_SECS);
+    loop {
+        if let Err(e) = poll(&workflow_id, &node).await {
+            error!("IMAP 触发器 {}/{} 轮询失败: {}", workflow_id, node.id, e);
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+async fn poll(workflow_id: &str, node: &Node) -> anyhow::Result<()> {
+    let host = node.config.get("host").map(|v| v.trim()).filter(|v| !v.is_empty()).ok_or_else(|| anyhow!("IMAP host 为空"))?;
+    // tls（默认，隐式 TLS）、starttls 或 none（明文，适用于本地测试服务器）
+    let security = node.config.get("security").map(|v| v.trim().to_lowercase()).unwrap_or("tls".to_string());
+    let default_port = if security == "tls" { 993 } else { 143 };
+    let port = node.config.get("port").and_then(|v| v.trim().parse::<u16>().ok()).unwrap_or(default_port);
+    let username = node.config.get("username").map(|v| v.trim()).unwrap_or_default();
+    let password = node.config.get("password").map(|v| v.as_str()).unwrap_or_default();
+    let folder = node.config.get("folder").map(|v| v.trim()).filter(|v| !v.is_empty()).unwrap_or("INBOX");
+    // 额外的 IMAP SEARCH 条件，例如 UNSEEN FROM "boss@example.com"
+    let criteria = search_criteria(node.config.get("search").map(|v| v.trim()).filter(|v| !v.is_empty()).unwrap_or("UNSEEN"))?;
+    // 处理后的动作：seen（默认，标记已读）、move（移动到 moveTo 文件夹）、delete 或 none
+    let after = node.config.get("afterProcess").map(|v| v.trim().to_lowercase()).unwrap_or("seen".to_string());
+    let move_to = node.config.get("moveTo").map(|v| v.trim()).filter(|v| !v.is_empty());
+    if after == "move" && move_to.is_none() {
+        bail!("afterProcess 为 move 时 moveTo 不能为空");
+    }
+
+    let mut client = Client::connect(host, port, &security).await?;
+    client.command(&format!("LOGIN {} {}", quote(username), quote(password))).await?;
+    let (lines, _) = client.command(&format!("SELECT {}", quote(folder))).await?;
+    let uid_validity = lines.iter().find_map(|line| line.split("[UIDVALIDITY ").nth(1)).and_then(|v| v.split(']').next()).and_then(|v| v.trim().parse::<u32>().ok()).unwrap_or_default();
+
+    let mut state: State = super::load_state(workflow_id, &node.id).and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default();
+    if state.uid_validity != uid_validity {
+        state = State { uid_validity, last_uid: 0 };
+    }
+
+    // UID n:* 在没有更大 UID 时会返回最后一封邮件，需要再次过滤
+    let (lines, _) = client
+        .command(&format!(
+            "UID SEARCH UID {}:* {}",
+            state.last_uid + 1,
+            criteria
+        ))
+        .await?;
+    let mut uids: Vec<u32> =
+        lines.iter().filter_map(|line| line.strip_prefix("* SEARCH")).flat_map(|line| line.split_whitespace()).filter_map(|uid| uid.parse().ok()).filter(|uid| *uid > state.last_uid).collect();
+    uids.sort_unstable();
+    if !uids.is_empty() {
+        info!(
+            "IMAP 触发器 {}/{} 发现 {} 封新邮件",
+            workflow_id,
+            node.id,
+            uids.len()
+        );
+    }
+
+    let mut expunge = false;
+    for uid in uids {
+        // BODY.PEEK 不会把邮件标记为已读
+        let (_, literals) = client.command(&format!("UID FETCH {} BODY.PEEK[]", uid)).await?;
+        if let Some(raw) = literals.into_iter().max_by_key(|literal| literal.len()) {
+            let data = parse_message(uid, &raw).await?;
+            let event = TriggerEvent { node_id: node.id.clone(), data };
+            // 工作流执行失败同样视为已处理，避免同一封邮件反复触发
+            if let Err(e) = run_trigger(workflow_id, event).await {
+                error!(
+                    "IMAP 触发器 {}/{} 处理邮件 uid={} 失败: {}",
+                    workflow_id, node.id, uid, e
+                );
+            }
+        }
+        // 先保存进度再标记或移动邮件，后续步骤失败时不会重复执行工作流
+        state.last_uid = uid;
+        super::save_state(workflow_id, &node.id, json!(state));
+
+        match after.as_str() {
+            "none" => {}
+            "delete" => {
+                client.command(&format!("UID STORE {} +FLAGS.SILENT (\\Deleted)", uid)).await?;
+                expunge = true;
+            }
+            // COPY + 删除兼容不支持 MOVE 扩展的服务器
+            "move" => {
+                client
+                    .command(&format!(
+                        "UID COPY {} {}",
+                        uid,
+                        quote(move_to.unwrap_or_default())
+                    ))
+                    .await?;
+                client.command(&format!("UID STORE {} +FLAGS.SILENT (\\Deleted)", uid)).await?;
+                expunge = true;
+            }
+            _ => {
+                client.command(&format!("UID STORE {} +FLAGS.SILENT (\\Seen)", uid)).await?;
+            }
+        }
+    }
+
+    if expunge {
+        client.command("EXPUNGE").await?;
+    }
+    let _ = client.command("LOGOUT").await;
+    Ok(())
+}
+
+/// 将邮件解析为触发数据，附件保存为二进制数据（随 binary.ttlHours 清理），下游节点按引用读取
+async fn parse_message(uid: u32, raw: &[u8]) -> anyhow::Result<Value> {
+    let message = MessageParser::default().parse(raw).ok_or_else(|| anyhow!("无法解析邮件 uid={}", uid))?;
+
+    let mut attachments = vec![];
+    for (index, part) in message.attachments().enumerate() {
+        let file_name = part.attachment_name().map(|name| name.to_string()).unwrap_or_else(|| format!("attachment-{}", index + 1));
+        let mime_type = part.content_type().map(|content_type| match content_type.subtype() {
+            Some(subtype) => format!("{}/{}", content_type.ctype(), subtype),
+            None => content_type.ctype().to_string(),
+        });
+        attachments.push(serde_json::to_value(Binary::save(
+            part.contents(),
+            Some(&file_name),
+            mime_type.as_deref(),
+        )?)?);
+    }
+
+    let first = |address: Option<&Address>| address.and_then(|address| address.first()).and_then(|addr| addr.address()).map(|v| v.to_string());
+    let all = |address: Option<&Address>| address.map(|address| address.iter().filter_map(|addr| addr.address()).map(|v| v.to_string()).collect::<Vec<_>>()).unwrap_or_default();
+    Ok(json!({
+        "uid": uid,
+        "messageId": message.message_id(),
+        "from": first(message.from()),
+        "fromName": message.from().and_then(|address| address.first()).and_then(|addr| addr.name()),
+        "to": all(message.to()),
+        "cc": all(message.cc()),
+        "subject": message.subject(),
+        "date": message.date().map(|date| date.to_rfc3339()),
+        "text": message.body_text(0),
+        "html": message.body_html(0),
+        "attachments": attachments,
+    }))
+}
+
+/// search 原样拼接到 UID SEARCH 命令中，不能包含换行等控制字符或以字面量 {N} 结尾，否则可以插入额外的命令
+fn search_criteria(criteria: &str) -> anyhow::Result<&str> {
+    if criteria.chars().any(char::is_control) {
+        bail!("search 不能包含换行等控制字符");
+    }
+    if criteria.ends_with('}') {
+        bail!("search 不能以 {{N}} 字面量结尾: {}", criteria);
+    }
+    Ok(criteria)
+}
+
+/// IMAP 带引号字符串
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// 最小化的 IMAP 客户端，只实现触发器需要的命令
+struct Client {
+    stream: BufReader<Box<dyn Stream>>,
+    tag: u32,
+}
+
+impl Client {
+    async fn connect(host: &str, port: u16, security: &str) -> anyhow::Result<Self> {
+        let tcp = TcpStream::connect((host, port)).await.map_err(|e| anyhow!("连接 IMAP 服务器 {}:{} 失败: {}", host, port, e))?;
+        let stream: Box<dyn Stream> = if security == "tls" { Box::new(tls(host, tcp).await?) } else { Box::new(tcp) };
+        let mut client = Client { stream: BufReader::new(stream), tag: 0 };
+        // 服务器问候
+        let (greeting, _) = client.read_response().await?;
+        if !greeting.starts_with("* OK") && !greeting.starts_with("* PREAUTH") {
+            bail!("IMAP 服务器拒绝连接: {}", greeting);
+        }
+        if security == "starttls" {
+            client.command("STARTTLS").await?;
+            let stream = client.stream.into_inner();
+            client.stream = BufReader::new(Box::new(tls(host, stream).await?));
+        }
+        Ok(client)
+    }
+
+    /// 发送命令并读取到对应标签的完成响应，返回非标签响应行与其中的字面量
+    async fn command(&mut self, command: &str) -> anyhow::Result<(Vec<String>, Vec<Vec<u8>>)> {
+        self.tag += 1;
+        let tag = format!("A{:04}", self.tag);
+        let stream = self.stream.get_mut();
+        stream.write_all(format!("{} {}\r\n", tag, command).as_bytes()).await?;
+        stream.flush().await?;
+
+        let (mut lines, mut literals) = (vec![], vec![]);
+        loop {
+            let (line, line_literals) = self.read_response().await?;
+            literals.extend(line_literals);
+            if let Some(status) = line.strip_prefix(&tag).map(|v| v.trim_start()) {
+                if status.starts_with("OK") {
+                    return Ok((lines, literals));
+                }
+                // 不回显命令本身，避免 LOGIN 的密码出现在日志中
+                bail!(
+                    "IMAP 命令 {} 失败: {}",
+                    command.split(' ').next().unwrap_or_default(),
+                    status
+                );
+            }
+            lines.push(line);
+        }
+    }
+
+    /// 读取一条响应，行尾的 {N} 表示紧随其后的 N 字节字面量
+    async fn read_response(&mut self) -> anyhow::Result<(String, Vec<Vec<u8>>)> {
+        let (mut text, mut literals) = (String::new(), vec![]);
+        loop {
+            let mut line = Vec::new();
+            if self.stream.read_until(b'\n', &mut line).await? == 0 {
+                bail!("IMAP 连接已关闭");
+            }
+            let line = String::from_utf8_lossy(&line).trim_end_matches(['\r', '\n']).to_string();
+            let literal_len = line.strip_suffix('}').and_then(|v| v.rsplit_once('{')).and_then(|(_, len)| len.parse::<usize>().ok());
+            text.push_str(&line);
+            let Some(len) = literal_len else {
+                return Ok((text, literals));
+            };
+            let mut literal = vec![0; len];
+            self.stream.read_exact(&mut literal).await?;
+            literals.push(literal);
+        }
+    }
+}
+
+async fn tls<S: Stream>(host: &str, stream: S) -> anyhow::Result<TlsStream<S>> {
+    let config = tls::client_config(None)?;
+    let domain = ServerName::try_from(host.to_string())?;
+    Ok(TlsConnector::from(Arc::new(config)).connect(domain, stream).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{net::TcpListener, task::JoinHandle};
+
+    use super::*;
+
+    const MAIL: &str = "From: Boss <boss@example.com>\r\nTo: a@example.com, b@example.com\r\nSubject: report\r\nMessage-ID: <1@example.com>\r\nMIME-Version: 1.0\r\nContent-Type: multipart/mixed; boundary=b\r\n\r\n--b\r\nContent-Type: text/plain\r\n\r\nhello\r\n--b\r\nContent-Type: text/csv\r\nContent-Disposition: attachment; filename=\"../report.csv\"\r\n\r\na,b\r\n--b--\r\n";
+
+    /// 依次应答每条命令的 IMAP 服务器，应答中的 {tag} 替换为命令的标签，结束时返回收到的命令（不含标签）
+    async fn serve(replies: Vec<&'static str>) -> (u16, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut socket = BufReader::new(socket);
+            socket.get_mut().write_all(b"* OK IMAP ready\r\n").await.unwrap();
+            let mut commands = vec![];
+            for reply in replies {
+                let mut line = String::new();
+                socket.read_line(&mut line).await.unwrap();
+                let (tag, command) = line.trim_end().split_once(' ').unwrap();
+                commands.push(command.to_string());
+                socket.get_mut().write_all(reply.replace("{tag}", tag).as_bytes()).await.unwrap();
+            }
+            commands
+        });
+        (port, handle)
+    }
+
+    #[tokio::test]
+    async fn client_reads_literals_and_tagged_status() {
+        let (port, server) = serve(vec![
+            "* 3 EXISTS\r\n* OK [UIDVALIDITY 7] UIDs valid\r\n{tag} OK [READ-WRITE] SELECT completed\r\n",
+            "* 1 FETCH (UID 5 BODY[] {5}\r\nhello)\r\n{tag} OK FETCH completed\r\n",
+            "{tag} NO [AUTHENTICATIONFAILED] invalid credentials\r\n",
+        ])
+        .await;
+        let mut client = Client::connect("127.0.0.1", port, "none").await.unwrap();
+
+        let (lines, literals) = client.command("SELECT \"INBOX\"").await.unwrap();
+        assert_eq!(lines, ["* 3 EXISTS", "* OK [UIDVALIDITY 7] UIDs valid"]);
+        assert!(literals.is_empty());
+        let (lines, literals) = client.command("UID FETCH 5 BODY.PEEK[]").await.unwrap();
+        assert_eq!(lines, ["* 1 FETCH (UID 5 BODY[] {5})"]);
+        assert_eq!(literals, [b"hello".to_vec()]);
+        let error = client.command(&format!("LOGIN {} {}", quote("u"), quote("p\"w"))).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "IMAP 命令 LOGIN 失败: NO [AUTHENTICATIONFAILED] invalid credentials"
+        );
+
+        assert_eq!(server.await.unwrap(), [
+            "SELECT \"INBOX\"",
+            "UID FETCH 5 BODY.PEEK[]",
+            r#"LOGIN "u" "p\"w""#
+        ]);
+    }
+
+    #[tokio::test]
+    async fn rejects_refused_greeting() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket.write_all(b"* BYE too many connections\r\n").await.unwrap();
+        });
+        let error = Client::connect("127.0.0.1", port, "none").await.err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "IMAP 服务器拒绝连接: * BYE too many connections"
+        );
+    }
+
+    #[tokio::test]
+    async fn parses_message_and_stores_attachments_as_binaries() {
+        let data = parse_message(5, MAIL.as_bytes()).await.unwrap();
+        assert_eq!(data["from"], "boss@example.com");
+        assert_eq!(data["fromName"], "Boss");
+        assert_eq!(data["to"], json!(["a@example.com", "b@example.com"]));
+        assert_eq!(data["subject"], "report");
+        assert_eq!(data["text"], "hello");
+
+        let attachment = Binary::parse(&data["attachments"][0].to_string()).unwrap();
+        assert_eq!(
+            (
+                attachment.file_name.as_str(),
+                attachment.mime_type.as_str(),
+                attachment.size
+            ),
+            ("report.csv", "text/csv", 3)
+        );
+        assert_eq!(attachment.read().unwrap(), b"a,b");
+    }
+
+    #[test]
+    fn rejects_injected_search_criteria() {
+        assert_eq!(
+            search_criteria(r#"UNSEEN FROM "boss@example.com""#).unwrap(),
+            r#"UNSEEN FROM "boss@example.com""#
+        );
+        assert_eq!(
+            search_criteria("UNSEEN\r\nA0001 DELETE INBOX").unwrap_err().to_string(),
+            "search 不能包含换行等控制字符"
+        );
+        assert_eq!(
+            search_criteria("SUBJECT {5}").unwrap_err().to_string(),
+            "search 不能以 {N} 字面量结尾: SUBJECT {5}"
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use std::{
+    collections::{HashMap, HashSet}, sync::{Mutex, OnceLock}
+};
+
+use log::{error, info, warn};
+use serde_json::Value;
+use tokio::task::JoinHandle;
+
+use super::model::{Node, Workflow};
+
+mod imap;
+mod mqtt;
+mod redis;
+mod websocket;
+
+static LISTENERS: OnceLock<Mutex<HashMap<(String, String), Listener>>> = OnceLock::new();
+static STATES: OnceLock<Mutex<HashMap<String, Value>>> = OnceLock::new();
+static STATE_FILE: &str = "trigger_state.json";
+
+/// 正在运行的触发器监听任务，配置变化时重启
+struct Listener {
+    config: HashMap<String, String>,
+    handle: JoinHandle<()>,
+}
+
+/// 类型以 -trigger 结尾的节点为触发器节点，由后台任务监听外部事件并启动工作流
+pub fn is_trigger(kind: &str) -> bool {
+    kind.ends_with("-trigger")
+}
+
+/// 按已保存的工作流启动、重启或停止触发器监听任务，在启动时以及工作流保存、删除后调用
+pub fn sync(workflows: &[Workflow]) {
+    let mut listeners = LISTENERS.get_or_init(Default::default).lock().unwrap();
+    let mut active = HashSet::new();
+    for workflow in workflows {
+        let Some(workflow_id) = &workflow.id else {
+            continue;
+        };
+        for node in workflow.nodes.iter().filter(|node| is_trigger(&node.kind)) {
+            let key = (workflow_id.clone(), node.id.clone());
+            active.insert(key.clone());
+            if listeners.get(&key).is_some_and(|listener| listener.config == node.config && !listener.handle.is_finished()) {
+                continue;
+            }
+            if let Some(listener) = listeners.remove(&key) {
+                listener.handle.abort();
+            }
+            if let Some(handle) = spawn(workflow_id.clone(), node.clone()) {
+                listeners.insert(key, Listener { config: node.config.clone(), handle });
+            }
+        }
+    }
+    listeners.retain(|(workflow_id, node_id), listener| {
+        let keep = active.contains(&(workflow_id.clone(), node_id.clone()));
+        if !keep {
+            info!("停止触发器 {}/{}", workflow_id, node_id);
+            listener.handle.abort();
+        }
+        keep
+    });
+}
+
+fn spawn(workflow_id: String, node: Node) -> Option<JoinHandle<()>> {
+    info!("启动触发器 {}/{} ({})", workflow_id, node.id, node.kind);
+    match node.kind.as_str() {
+        "imap-trigger" => Some(tokio::spawn(imap::listen(workflow_id, node))),
+        "mqtt-trigger" => Some(tokio::spawn(mqtt::listen(workflow_id, node))),
+        "redis-trigger" => Some(tokio::spawn(redis::listen(workflow_id, node))),
+        "websocket-trigger" => Some(tokio::spawn(websocket::listen(workflow_id, node))),
+        _ => {
+            warn!("未知的触发器类型: {}", node.kind);
+            None
+        }
+    }
+}
+
+// 触发器状态持久化，按 "工作流 id/节点 id" 保存在 trigger_state.json 中，重启后继续使用
+
+fn load_states() -> HashMap<String, Value> {
+    std::fs::read_to_string(STATE_FILE).ok().and_then(|json_string| serde_json::from_str(&json_string).ok()).unwrap_or_default()
+}
+
+pub fn load_state(workflow_id: &str, node_id: &str) -> Option<Value> {
+    let states = STATES.get_or_init(|| Mutex::new(load_states())).lock().unwrap();
+    states.get(&format!("{}/{}", workflow_id, node_id)).cloned()
+}
+
+pub fn save_state(workflow_id: &str, node_id: &str, state: Value) {
+    let mut states = STATES.get_or_init(|| Mutex::new(load_states())).lock().unwrap();
+    states.insert(format!("{}/{}", workflow_id, node_id), state);
+    let result = serde_json::to_string_pretty(&*states).map_err(anyhow::Error::from).and_then(|json_string| Ok(std::fs::write(STATE_FILE, json_string)?));
+    // 保存失败时进度只保留在内存中，重启后可能重复处理
+    if let Err(e) = result {
+        error!("保存 {} 失败: {}", STATE_FILE, e);
+    }
+}
@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use anyhow::bail;
+use log::{error, info};
+use serde_json::json;
+
+use super::super::{context::TriggerEvent, model::Node, node::mqtt, run_trigger};
+
+const RETRY_DELAY_SECS: u64 = 5;
+
+/// 订阅 MQTT 主题，每条消息执行一次工作流。连接与其他 mqtt 节点、触发器共享，断线重连与重新订阅由共享连接负责
+pub async fn listen(workflow_id: String, node: Node) {
+    loop {
+        if let Err(e) = subscribe(&workflow_id, &node).await {
+            error!("MQTT 触发器 {}/{} 出错: {}", workflow_id, node.id, e);
+        }
+        tokio::time::sleep(Duration::from_secs(RETRY_DELAY_SECS)).await;
+    }
+}
+
+/// topics 以逗号或换行分隔，支持 + 与 # 通配符
+async fn subscribe(workflow_id: &str, node: &Node) -> anyhow::Result<()> {
+    let topics: Vec<String> = node.config.get("topics").map(|v| v.split([',', '\n']).map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()).unwrap_or_default();
+    if topics.is_empty() {
+        bail!("topics 为空");
+    }
+    let broker = mqtt::broker(node)?;
+    let mut subscription = broker.subscribe(topics.clone(), mqtt::qos(node)?).await?;
+    info!(
+        "MQTT 触发器 {}/{} 已订阅 {:?}",
+        workflow_id, node.id, topics
+    );
+
+    while let Some(publish) = subscription.messages.recv().await {
+        let data = json!({
+            "topic": publish.topic,
+            "payload": mqtt::payload_value(&publish.payload)?,
+            "qos": publish.qos as u8,
+            "retain": publish.retain,
+        });
+        if let Err(e) = run_trigger(workflow_id, TriggerEvent { node_id: node.id.clone(), data }).await {
+            error!(
+                "MQTT 触发器 {}/{} 处理消息失败: {}",
+                workflow_id, node.id, e
+            );
+        }
+    }
+    bail!("订阅已结束")
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
+use log::{error, info};
+use redis::{
+    AsyncCommands, streams::{StreamRangeReply, StreamReadOptions, StreamReadReply}
+};
+use serde_json::{Map, Value, json};
+use tokio_stream::StreamExt;
+
+use super::super::{context::TriggerEvent, model::Node, run_trigger};
+
+const RECONNECT_DELAY_SECS: u64 = 5;
+static DEFAULT_URL: &str = "redis://127.0.0.1:6379";
+
+/// 订阅 Redis 频道（mode=pubsub，默认）或读取 Stream（mode=stream），每条消息执行一次工作流。
+/// 连接断开后等待数秒重连
+pub async fn listen(workflow_id: String, node: Node) {
+    let mode = node.config.get("mode").map(|v| v.trim().to_lowercase()).unwrap_or_default();
+    loop {
+        let result = match mode.as_str() {
+            "stream" => stream(&workflow_id, &node).await,
+            _ => pubsub(&workflow_id, &node).await,
+        };
+        if let Err(e) = result {
+            error!("Redis 触发器 {}/{} 出错: {}", workflow_id, node.id, e);
+        }
+        tokio::time::sleep(Duration::from_secs(RECONNECT_DELAY_SECS)).await;
+    }
+}
+
+fn client(node: &Node) -> anyhow::Result<redis::Client> {
+    let url = node.config.get("url").map(|v| v.trim()).filter(|v| !v.is_empty()).unwrap_or(DEFAULT_URL);
+    redis::Client::open(url).map_err(|e| anyhow!("Redis 地址无效: {}", e))
+}
+
+/// 频道以逗号或换行分隔，包含 * ? [ 的按模式订阅
+async fn pubsub(workflow_id: &str, node: &Node) -> anyhow::Result<()> {
+    let channels: Vec<&str> = node.config.get("channels").map(|v| v.split([',', '\n']).map(|s| s.trim()).filter(|s| !s.is_empty()).collect()).unwrap_or_default();
+    if channels.is_empty() {
+        bail!("channels 为空");
+    }
+    let mut pubsub = client(node)?.get_async_pubsub().await?;
+    for channel in &channels {
+        if channel.contains(['*', '?', '[']) {
+            pubsub.psubscribe(*channel).await?;
+        } else {
+            pubsub.subscribe(*channel).await?;
+        }
+    }
+    info!(
+        "Redis 触发器 {}/{} 已订阅 {:?}",
+        workflow_id, node.id, channels
+    );
+
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        let payload: String = message.get_payload().unwrap_or_default();
+        let data = json!({
+            "channel": message.get_channel_name(),
+            "pattern": message.get_pattern::<Option<String>>().ok().flatten(),
+            "message": payload,
+        });
+        if let Err(e) = run_trigger(workflow_id, TriggerEvent { node_id: node.id.clone(), data }).await {
+            error!(
+                "Redis 触发器 {}/{} 处理消息失败: {}",
+                workflow_id, node.id, e
+            );
+        }
+    }
+    bail!("订阅连接已断开")
+}
+
+/// 从上次处理的消息 id 继续读取 Stream，首次启动时只处理之后写入的消息
+async fn stream(workflow_id: &str, node: &Node) -> anyhow::Result<()> {
+    let key = node.config.get("stream").map(|v| v.trim()).filter(|v| !v.is_empty()).ok_or_else(|| anyhow!("stream 为空"))?;
+    // 阻塞读取会占用连接，因此不使用节点共享的连接
+    let mut con = client(node)?.get_multiplexed_async_connection().await?;
+
+    let mut last_id = match super::load_state(workflow_id, &node.id).as_ref().and_then(|state| state.get("lastId")).and_then(|id| id.as_str()) {
+        Some(id) => id.to_string(),
+        None => {
+            let latest: StreamRangeReply = con.xrevrange_count(key, "+", "-", 1).await?;
+            latest.ids.first().map(|entry| entry.id.clone()).unwrap_or("0-0".to_string())
+        }
+    };
+    info!(
+        "Redis 触发器 {}/{} 从 {} 读取 Stream {}",
+        workflow_id, node.id, last_id, key
+    );
+
+    let options = StreamReadOptions::default().block(5000).count(100);
+    loop {
+        let reply: Option<StreamReadReply> = con.xread_options(&[key], &[&last_id], &options).await?;
+        for entry in reply.into_iter().flat_map(|reply| reply.keys).flat_map(|stream| stream.ids) {
+            let fields: Map<String, Value> = entry
+                .map
+                .iter()
+                .map(|(field, value)| {
+                    (
+                        field.clone(),
+                        json!(redis::from_redis_value::<String>(value).unwrap_or_default()),
+                    )
+                })
+                .collect();
+            let data = json!({ "stream": key, "id": entry.id, "fields": fields });
+            if let Err(e) = run_trigger(workflow_id, TriggerEvent { node_id: node.id.clone(), data }).await {
+                error!(
+                    "Redis 触发器 {}/{} 处理消息 {} 失败: {}",
+                    workflow_id, node.id, entry.id, e
+                );
+            }
+            last_id = entry.id;
+            super::save_state(workflow_id, &node.id, json!({ "lastId": last_id }));
+        }
+    }
+}
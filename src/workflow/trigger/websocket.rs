@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail};
+use futures::{SinkExt, StreamExt};
+use log::{error, info};
+use serde_json::json;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::super::{context::TriggerEvent, model::Node, node::websocket, run_trigger};
+
+const CONNECT_TIMEOUT_SECS: u64 = 30;
+const MIN_BACKOFF_SECS: u64 = 1;
+const MAX_BACKOFF_SECS: u64 = 60;
+/// 连接保持超过该时长后断开视为偶发断线，重连等待时间从头计算
+const STABLE_SECS: u64 = 60;
+
+/// 保持到 url 的 WebSocket 连接，连接后发送 messages（如订阅消息），每收到一条消息执行一次工作流。
+/// 断开后按 1 秒起、每次翻倍、最长 60 秒的间隔重连
+pub async fn listen(workflow_id: String, node: Node) {
+    let mut backoff = MIN_BACKOFF_SECS;
+    loop {
+        let started = Instant::now();
+        if let Err(e) = subscribe(&workflow_id, &node).await {
+            error!("WebSocket 触发器 {}/{} 出错: {}", workflow_id, node.id, e);
+        }
+        if started.elapsed() >= Duration::from_secs(STABLE_SECS) {
+            backoff = MIN_BACKOFF_SECS;
+        }
+        info!(
+            "WebSocket 触发器 {}/{} {} 秒后重连",
+            workflow_id, node.id, backoff
+        );
+        tokio::time::sleep(Duration::from_secs(backoff)).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF_SECS);
+    }
+}
+
+/// pingInterval 大于 0 时按该秒数发送 ping 保持连接
+async fn subscribe(workflow_id: &str, node: &Node) -> anyhow::Result<()> {
+    let url = node.config.get("url").map(|v| v.trim().to_string()).unwrap_or_default();
+    let ping_interval = match node.config.get("pingInterval").map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        Some(v) => v.parse::<u64>().map_err(|_| anyhow!("pingInterval 不是合法的秒数: {}", v))?,
+        None => 0,
+    };
+    let mut socket = websocket::connect(node, Duration::from_secs(CONNECT_TIMEOUT_SECS)).await?;
+    for message in websocket::messages(node) {
+        socket.send(Message::text(message)).await?;
+    }
+    info!(
+        "WebSocket 触发器 {}/{} 已连接 {}",
+        workflow_id, node.id, url
+    );
+
+    let mut ping = tokio::time::interval(Duration::from_secs(ping_interval.max(1)));
+    ping.tick().await;
+    loop {
+        let message = tokio::select! {
+            message = socket.next() => message,
+            _ = ping.tick(), if ping_interval > 0 => {
+                socket.send(Message::Ping(Default::default())).await?;
+                continue;
+            }
+        };
+        let message = match message {
+            Some(message) => message?,
+            None => bail!("连接已断开"),
+        };
+        if let Message::Close(frame) = &message {
+            bail!("服务端关闭连接: {:?}", frame);
+        }
+        let Some(value) = websocket::message_value(message)? else {
+            continue;
+        };
+        let data = json!({ "url": url, "message": value });
+        if let Err(e) = run_trigger(workflow_id, TriggerEvent { node_id: node.id.clone(), data }).await {
+            error!(
+                "WebSocket 触发器 {}/{} 处理消息失败: {}",
+                workflow_id, node.id, e
+            );
+        }
+    }
+}